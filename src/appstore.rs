@@ -0,0 +1,130 @@
+use std::sync::Arc;
+use crate::extract::{self, IapListing, Page};
+use crate::pricing::{InAppPurchase, Pricing, RegionError, RegionOutcome};
+use crate::regions::Region;
+use crate::transport::{Transport, TransportError};
+
+/// Where storefront pages are fetched from.
+pub const DEFAULT_STORE_URL: &str = "https://apps.apple.com";
+
+/// Where further pages of an app's IAP list are fetched from.
+pub const DEFAULT_CATALOG_URL: &str = "https://amp-api.apps.apple.com";
+
+/// Upper bound on catalog pages followed per storefront, in case Apple's
+/// `next` links never run out.
+const MAX_CATALOG_PAGES: usize = 50;
+
+/// Fetches app pages from one App Store host and hands them to `extract`.
+#[derive(Clone)]
+pub(crate) struct Storefront {
+    pub transport: Arc<dyn Transport>,
+    pub base_url: String,
+    pub catalog_url: String,
+    /// Follow the IAP list past the `top-in-apps` shortlist.
+    pub full_catalog: bool,
+}
+
+impl Storefront {
+    fn app_url(&self, app_id: &str, region_code: &str) -> String {
+        format!(
+            "{}/{}/app/id{}",
+            self.base_url.trim_end_matches('/'),
+            region_code.to_lowercase(),
+            app_id
+        )
+    }
+
+    /// Fetch the app's storefront page. A 404 is returned as a page (the app
+    /// isn't sold there); any other non-success status is an error.
+    async fn fetch_page(&self, app_id: &str, region_code: &str) -> Result<Page, RegionError> {
+        let res = self
+            .transport
+            .get(&self.app_url(app_id, region_code))
+            .await
+            .map_err(|e| match e {
+                TransportError::Timeout => RegionError::TimedOut,
+                TransportError::Request(msg) => RegionError::Fetch(msg),
+            })?;
+        match res.status {
+            _ if res.is_success() => {}
+            404 => {}
+            429 => return Err(RegionError::RateLimited),
+            status => return Err(RegionError::Fetch(format!("HTTP {}", status))),
+        }
+        Ok(Page::new(res.status, res.body))
+    }
+
+    pub async fn app_name(&self, app_id: &str, region_code: &str) -> Option<String> {
+        extract::app_name(&self.fetch_page(app_id, region_code).await.ok()?)
+    }
+
+    /// The app's IAP listing in `region`: the page's shortlist, then, with
+    /// `full_catalog` on, every further page Apple links to, fetched with the
+    /// page's catalog token. A page that can't be fetched or read ends the
+    /// walk early and leaves the listing marked incomplete, with
+    /// [`IapListing::truncated`] saying why.
+    pub async fn iap_listing(&self, app_id: &str, region: &Region) -> Result<Option<IapListing>, RegionError> {
+        let Some(mut listing) = extract::iap_listing(&self.fetch_page(app_id, region.code).await?)? else {
+            return Ok(None);
+        };
+        if !self.full_catalog {
+            return Ok(Some(listing));
+        }
+        let authorization = listing.token.as_ref().map(|token| format!("Bearer {}", token));
+        let mut headers = vec![("Origin", DEFAULT_STORE_URL)];
+        if let Some(authorization) = &authorization {
+            headers.push(("Authorization", authorization));
+        }
+        for _ in 0..MAX_CATALOG_PAGES {
+            let Some(next) = listing.next.clone() else { break };
+            let url = if next.starts_with('/') {
+                format!("{}{}", self.catalog_url.trim_end_matches('/'), next)
+            } else {
+                next
+            };
+            let failure = match self.transport.get_with_headers(&url, &headers).await {
+                Ok(res) if res.is_success() => match listing.extend_from_page(&res.body) {
+                    Ok(()) => continue,
+                    Err(e) => e.to_string(),
+                },
+                Ok(res) => format!("catalog page answered HTTP {}", res.status),
+                Err(e) => format!("catalog page failed: {}", e),
+            };
+            listing.truncated = Some(failure);
+            return Ok(Some(listing));
+        }
+        if listing.next.is_some() {
+            listing.truncated = Some(format!("stopped after {} catalog pages", MAX_CATALOG_PAGES));
+        }
+        Ok(Some(listing))
+    }
+
+    pub async fn iap_outcome(
+        &self,
+        app_id: &str,
+        region: &Region,
+        selected: &InAppPurchase,
+        known: &[InAppPurchase],
+    ) -> Result<RegionOutcome, RegionError> {
+        match self.iap_listing(app_id, region).await? {
+            Some(listing) => listing.price_of(region, selected, known),
+            None => Ok(RegionOutcome::NotAvailable),
+        }
+    }
+
+    pub async fn all_iap_prices(
+        &self,
+        app_id: &str,
+        region: &Region,
+        known: &[InAppPurchase],
+    ) -> Result<Option<Vec<Pricing>>, RegionError> {
+        self.iap_listing(app_id, region)
+            .await?
+            .map(|listing| listing.prices(region, known))
+            .transpose()
+    }
+
+    pub async fn app_outcome(&self, app_id: &str, region: &Region) -> Result<RegionOutcome, RegionError> {
+        extract::app_outcome(&self.fetch_page(app_id, region.code).await?, region)
+    }
+}
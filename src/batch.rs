@@ -0,0 +1,139 @@
+//! Lists of apps to price in one run, read from a plain-text, CSV or TOML
+//! file.
+//!
+//! Every format names an app (App Store URL or numeric App ID), optionally
+//! the in-app purchases to price (product IDs) and the storefronts to fetch
+//! (region codes):
+//!
+//! ```text
+//! # apps.txt: APP[@PRODUCT_ID[,PRODUCT_ID...]] [REGION,REGION...]
+//! 1234567890
+//! 1234567890@com.example.pro US,JP,GB
+//! https://apps.apple.com/us/app/id284882215
+//! ```
+//!
+//! ```text
+//! app,iaps,regions
+//! 1234567890,com.example.pro;com.example.plus,US;JP
+//! 284882215,,
+//! ```
+//!
+//! ```toml
+//! [[apps]]
+//! app = "1234567890"
+//! iaps = ["com.example.pro"]
+//! regions = ["US", "JP"]
+//! ```
+
+use std::path::Path;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use crate::pricing::Pricing;
+
+/// One line, row or `[[apps]]` table of a batch file.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct BatchEntry {
+    /// App Store URL or numeric App ID.
+    pub app: String,
+    /// Product IDs of the in-app purchases to price; every listed IAP, or
+    /// the app itself when it has none, when empty.
+    #[serde(default)]
+    pub iaps: Vec<String>,
+    /// Region codes to fetch; the command line's selection when empty.
+    #[serde(default)]
+    pub regions: Vec<String>,
+}
+
+/// One app's prices in a combined report.
+#[derive(Serialize, Debug, Clone)]
+pub struct AppPrices {
+    pub app_id: String,
+    pub app_name: Option<String>,
+    pub pricing: Vec<Pricing>,
+}
+
+/// Read a batch file, picking the format from its extension: `.toml`,
+/// `.csv`, or plain text for anything else.
+pub fn read_batch_file(path: &Path) -> Result<Vec<BatchEntry>> {
+    let contents =
+        std::fs::read_to_string(path).with_context(|| format!("Unable to read batch file {}", path.display()))?;
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or_default().to_ascii_lowercase();
+    let entries = match extension.as_str() {
+        "toml" => parse_batch_toml(&contents),
+        "csv" => parse_batch_csv(&contents),
+        _ => parse_batch_text(&contents),
+    }
+    .with_context(|| format!("Invalid batch file {}", path.display()))?;
+    if entries.is_empty() {
+        bail!("Batch file {} lists no apps", path.display());
+    }
+    Ok(entries)
+}
+
+/// Split `values` on `sep`, dropping blanks.
+fn list(values: &str, sep: char) -> Vec<String> {
+    values.split(sep).map(str::trim).filter(|v| !v.is_empty()).map(str::to_string).collect()
+}
+
+/// `APP[@PRODUCT_ID[,PRODUCT_ID...]] [REGION,REGION...]` per line. Blank
+/// lines and `#` comments are skipped.
+pub fn parse_batch_text(contents: &str) -> Result<Vec<BatchEntry>> {
+    let mut entries = Vec::new();
+    for (n, line) in contents.lines().enumerate() {
+        let line = line.split_once('#').map_or(line, |(before, _)| before).trim();
+        if line.is_empty() {
+            continue;
+        }
+        let mut words = line.split_whitespace();
+        let target = words.next().expect("line isn't blank");
+        let regions = words.next().map(|r| list(r, ',')).unwrap_or_default();
+        if words.next().is_some() {
+            bail!("Line {} has more than an app and a list of regions", n + 1);
+        }
+        let (app, iaps) = match target.rsplit_once('@') {
+            Some((app, iaps)) => (app, list(iaps, ',')),
+            None => (target, Vec::new()),
+        };
+        entries.push(BatchEntry { app: app.to_string(), iaps, regions });
+    }
+    Ok(entries)
+}
+
+/// A CSV file with an `app` column and optional `iaps` and `regions`
+/// columns, whose values are separated by `;`.
+pub fn parse_batch_csv(contents: &str) -> Result<Vec<BatchEntry>> {
+    let mut lines = contents.lines().map(str::trim).filter(|l| !l.is_empty() && !l.starts_with('#'));
+    let header: Vec<String> = lines
+        .next()
+        .context("Empty batch file")?
+        .split(',')
+        .map(|h| h.trim().to_ascii_lowercase())
+        .collect();
+    let column = |name: &str| header.iter().position(|h| h == name);
+    let app_col = column("app").context("CSV batch file has no `app` column")?;
+    let (iaps_col, regions_col) = (column("iaps"), column("regions"));
+    lines
+        .enumerate()
+        .map(|(n, line)| {
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            let field = |col: Option<usize>| col.and_then(|i| fields.get(i).copied()).unwrap_or_default();
+            let app = field(Some(app_col));
+            if app.is_empty() {
+                bail!("Row {} has no app", n + 2);
+            }
+            Ok(BatchEntry { app: app.to_string(), iaps: list(field(iaps_col), ';'), regions: list(field(regions_col), ';') })
+        })
+        .collect()
+}
+
+/// A TOML file with an `[[apps]]` table per app.
+pub fn parse_batch_toml(contents: &str) -> Result<Vec<BatchEntry>> {
+    #[derive(Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct BatchFile {
+        #[serde(default)]
+        apps: Vec<BatchEntry>,
+    }
+    Ok(toml::from_str::<BatchFile>(contents)?.apps)
+}
@@ -0,0 +1,140 @@
+//! An on-disk cache of responses, so repeated runs don't refetch every
+//! storefront page and exchange-rate table.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use crate::transport::{Response, Transport, TransportError};
+
+/// How long a cached response is used before it's fetched again.
+pub const DEFAULT_CACHE_TTL: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// How the cache is consulted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CacheMode {
+    /// Serve fresh entries, fetch and store everything else.
+    #[default]
+    Normal,
+    /// Serve cached entries however old they are and never touch the network.
+    Offline,
+    /// Fetch everything again and store the new responses.
+    Refresh,
+}
+
+/// Where cached responses live and how long they stay fresh.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cache {
+    pub dir: PathBuf,
+    pub ttl: Duration,
+    pub mode: CacheMode,
+}
+
+impl Cache {
+    /// A cache in `dir` with the default TTL.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Cache { dir: dir.into(), ttl: DEFAULT_CACHE_TTL, mode: CacheMode::Normal }
+    }
+
+    /// `$XDG_CACHE_HOME/appstore_pricing`, or the platform's equivalent.
+    pub fn default_dir() -> Option<PathBuf> {
+        dirs::cache_dir().map(|d| d.join("appstore_pricing"))
+    }
+
+    /// The stored response for `url`, if any, and whether it's within the TTL.
+    pub fn lookup(&self, url: &str) -> Option<(Response, bool)> {
+        let entry: Entry = serde_json::from_str(&fs::read_to_string(self.path(url)).ok()?).ok()?;
+        // The file name is only a hash, so make sure it's really this URL.
+        if entry.url != url {
+            return None;
+        }
+        let age = (Utc::now() - entry.stored_at).to_std().unwrap_or_default();
+        let response = Response { status: entry.status, headers: entry.headers, body: entry.body };
+        Some((response, age <= self.ttl))
+    }
+
+    /// Remember `res` as the answer for `url`. Only successes and 404s (an app
+    /// missing from a storefront) are kept; anything else is worth retrying.
+    pub fn store(&self, url: &str, res: &Response) -> std::io::Result<()> {
+        if !res.is_success() && res.status != 404 {
+            return Ok(());
+        }
+        let entry = Entry {
+            url: url.to_string(),
+            stored_at: Utc::now(),
+            status: res.status,
+            headers: res.headers.clone(),
+            body: res.body.clone(),
+        };
+        fs::create_dir_all(&self.dir)?;
+        // Write then rename, so a concurrent reader never sees half a file.
+        let path = self.path(url);
+        let partial = path.with_extension(format!("{}.tmp", fastrand::u32(..)));
+        fs::write(&partial, serde_json::to_vec(&entry)?)?;
+        fs::rename(&partial, &path)
+    }
+
+    fn path(&self, url: &str) -> PathBuf {
+        self.dir.join(format!("{:016x}.json", fnv1a(url)))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    url: String,
+    stored_at: DateTime<Utc>,
+    status: u16,
+    headers: HashMap<String, String>,
+    body: String,
+}
+
+/// A stable file name for a URL; `DefaultHasher` may change between releases.
+fn fnv1a(s: &str) -> u64 {
+    s.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, b| (hash ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3))
+}
+
+/// A [`Transport`] that answers from a [`Cache`] where it can and stores
+/// what it had to fetch. Failing to write the cache never fails a request.
+pub struct Cached {
+    inner: Arc<dyn Transport>,
+    cache: Cache,
+}
+
+impl Cached {
+    pub fn new(inner: Arc<dyn Transport>, cache: Cache) -> Self {
+        Cached { inner, cache }
+    }
+}
+
+#[async_trait]
+impl Transport for Cached {
+    async fn get(&self, url: &str) -> Result<Response, TransportError> {
+        self.get_with_headers(url, &[]).await
+    }
+
+    /// Headers aren't part of the cache key: they only authorize the request.
+    async fn get_with_headers(&self, url: &str, headers: &[(&str, &str)]) -> Result<Response, TransportError> {
+        match self.cache.mode {
+            CacheMode::Offline => {
+                return self
+                    .cache
+                    .lookup(url)
+                    .map(|(res, _)| res)
+                    .ok_or_else(|| TransportError::Request(format!("not cached, and offline: {}", url)));
+            }
+            CacheMode::Normal => {
+                if let Some((res, true)) = self.cache.lookup(url) {
+                    return Ok(res);
+                }
+            }
+            CacheMode::Refresh => {}
+        }
+        let res = self.inner.get_with_headers(url, headers).await?;
+        let _ = self.cache.store(url, &res);
+        Ok(res)
+    }
+}
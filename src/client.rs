@@ -0,0 +1,308 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use anyhow::{anyhow, bail, Context, Result};
+use futures::stream::{self, Stream, StreamExt};
+use crate::appstore::{Storefront, DEFAULT_CATALOG_URL, DEFAULT_STORE_URL};
+use crate::cache::{Cache, Cached};
+use crate::extract::{self, IapListing, Page};
+use crate::link::{parse_app_link, AppLink, ParsedLink};
+use crate::fx::{FxProvider, OpenErApi};
+use crate::pricing::{convert_prices, InAppPurchase, Pricing, RegionError, RegionOutcome};
+use crate::ratelimit::{RateLimit, RateLimiter};
+use crate::regions::Region;
+use crate::retry::{RetryPolicy, Retrying};
+use crate::transport::{ReqwestTransport, Transport};
+
+/// How many storefronts are fetched at once unless configured otherwise.
+pub const DEFAULT_CONCURRENCY: usize = 8;
+
+/// Entry point for looking up App Store prices across storefronts.
+///
+/// ```no_run
+/// use appstore_pricing::{PricingClient, RegionOutcome, REGIONS};
+/// use futures::StreamExt;
+///
+/// # async fn run() -> anyhow::Result<()> {
+/// let client = PricingClient::new();
+/// let mut prices: Vec<_> = client
+///     .app_prices("284882215", REGIONS)
+///     .filter_map(|(_, outcome)| async move {
+///         match outcome {
+///             Ok(RegionOutcome::Found(p)) => Some(p),
+///             _ => None,
+///         }
+///     })
+///     .collect()
+///     .await;
+/// client.convert(&mut prices, "USD").await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct PricingClient {
+    store: Storefront,
+    fx: Arc<dyn FxProvider>,
+    /// The caller's transport, before rate limiting is layered on.
+    transport: Arc<dyn Transport>,
+    rate_limit: Option<RateLimit>,
+    retry: RetryPolicy,
+    cache: Option<Cache>,
+    concurrency: usize,
+}
+
+impl Default for PricingClient {
+    fn default() -> Self {
+        PricingClient::new()
+    }
+}
+
+impl fmt::Debug for PricingClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PricingClient")
+            .field("store_url", &self.store.base_url)
+            .field("catalog_url", &self.store.catalog_url)
+            .field("full_catalog", &self.store.full_catalog)
+            .field("fx", &self.fx)
+            .field("rate_limit", &self.rate_limit)
+            .field("retry", &self.retry)
+            .field("cache", &self.cache)
+            .field("concurrency", &self.concurrency)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PricingClient {
+    /// A client talking to apps.apple.com and open.er-api.com over `reqwest`.
+    pub fn new() -> Self {
+        let transport: Arc<dyn Transport> = Arc::new(ReqwestTransport::default());
+        let mut client = PricingClient {
+            store: Storefront {
+                transport: transport.clone(),
+                base_url: DEFAULT_STORE_URL.to_string(),
+                catalog_url: DEFAULT_CATALOG_URL.to_string(),
+                full_catalog: false,
+            },
+            fx: Arc::new(OpenErApi::default()),
+            transport,
+            rate_limit: Some(RateLimit::default()),
+            retry: RetryPolicy::default(),
+            cache: None,
+            concurrency: DEFAULT_CONCURRENCY,
+        };
+        client.rewire();
+        client
+    }
+
+    /// Send every request through `transport`, e.g. a [`ReqwestTransport`]
+    /// built from a custom [`HttpConfig`](crate::transport::HttpConfig).
+    pub fn with_transport(mut self, transport: impl Transport + 'static) -> Self {
+        self.transport = Arc::new(transport);
+        self.rewire();
+        self
+    }
+
+    /// Limit requests per host; `None` turns limiting off.
+    pub fn with_rate_limit(mut self, limit: Option<RateLimit>) -> Self {
+        self.rate_limit = limit;
+        self.rewire();
+        self
+    }
+
+    /// Retry, backoff and timeout settings for every request.
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry = policy;
+        self.rewire();
+        self
+    }
+
+    /// Answer repeat requests from an on-disk [`Cache`]; `None` (the
+    /// default) always fetches.
+    pub fn with_cache(mut self, cache: Option<Cache>) -> Self {
+        self.cache = cache;
+        self.rewire();
+        self
+    }
+
+    /// Fetch at most `n` storefronts at a time (at least one).
+    pub fn with_concurrency(mut self, n: usize) -> Self {
+        self.concurrency = n.max(1);
+        self
+    }
+
+    /// Rebuild the request stack: each retry waits its turn with the rate
+    /// limiter before its timeout starts, and cache hits skip both.
+    fn rewire(&mut self) {
+        let limiter = Arc::new(RateLimiter::new(self.rate_limit));
+        let retrying: Arc<dyn Transport> =
+            Arc::new(Retrying::new(self.transport.clone(), self.retry).with_limiter(limiter));
+        self.store.transport = match &self.cache {
+            Some(cache) => Arc::new(Cached::new(retrying, cache.clone())),
+            None => retrying,
+        };
+    }
+
+    /// Fetch storefront pages from `url` (e.g. `http://127.0.0.1:8080`)
+    /// instead of `https://apps.apple.com`.
+    pub fn with_store_url(mut self, url: impl Into<String>) -> Self {
+        self.store.base_url = url.into();
+        self
+    }
+
+    /// Look past the `top-in-apps` shortlist and page through the app's
+    /// whole IAP list wherever Apple links to more.
+    pub fn with_full_catalog(mut self, enabled: bool) -> Self {
+        self.store.full_catalog = enabled;
+        self
+    }
+
+    /// Fetch further pages of IAP lists from `url` instead of
+    /// `https://amp-api.apps.apple.com`.
+    pub fn with_catalog_url(mut self, url: impl Into<String>) -> Self {
+        self.store.catalog_url = url.into();
+        self
+    }
+
+    /// Fetch exchange rates from an open.er-api.com-compatible endpoint; the
+    /// base currency code is appended as the last path segment.
+    pub fn with_fx_url(mut self, url: impl Into<String>) -> Self {
+        self.fx = Arc::new(OpenErApi { url: url.into() });
+        self
+    }
+
+    /// Take exchange rates from `provider`, e.g. [`Ecb`](crate::fx::Ecb) or a
+    /// [`RatesFile`](crate::fx::RatesFile).
+    pub fn with_fx_provider(mut self, provider: impl FxProvider + 'static) -> Self {
+        self.fx = Arc::new(provider);
+        self
+    }
+
+    /// Parse an App ID or link, following `apple.co` short links to the store
+    /// page they redirect to.
+    pub async fn resolve_app_link(&self, input: &str) -> Result<AppLink> {
+        let short = match parse_app_link(input)? {
+            ParsedLink::App(link) => return Ok(link),
+            ParsedLink::Short(url) => url,
+        };
+        let res = self.store.transport.get(&short).await?;
+        if !res.is_success() {
+            bail!("Short link {} answered HTTP {}", short, res.status);
+        }
+        let target = extract::canonical_url(&Page::new(res.status, res.body))
+            .with_context(|| format!("Short link {} didn't lead to an App Store page", short))?;
+        match parse_app_link(&target)? {
+            ParsedLink::App(link) => Ok(link),
+            ParsedLink::Short(_) => bail!("Short link {} points at another short link", short),
+        }
+    }
+
+    /// The app's display name in the given storefront, if the page has one.
+    pub async fn app_name(&self, app_id: &str, region: &Region) -> Option<String> {
+        self.store.app_name(app_id, region.code).await
+    }
+
+    /// The in-app purchases listed on the app's page in `region`: the "top
+    /// in-app purchases", or with [`with_full_catalog`](Self::with_full_catalog)
+    /// as many as Apple will list.
+    pub async fn in_app_purchases(&self, app_id: &str, region: &Region) -> Result<Vec<InAppPurchase>> {
+        Ok(self.iap_listing(app_id, region).await?.purchases())
+    }
+
+    /// Like [`in_app_purchases`](Self::in_app_purchases), keeping track of
+    /// whether the list is complete.
+    pub async fn iap_listing(&self, app_id: &str, region: &Region) -> Result<IapListing> {
+        self.store
+            .iap_listing(app_id, region)
+            .await?
+            .ok_or_else(|| anyhow!("App {} is not available in the {} storefront", app_id, region.name))
+    }
+
+    /// Price of the app itself in one storefront.
+    pub async fn app_price(&self, app_id: &str, region: &Region) -> Result<RegionOutcome, RegionError> {
+        self.store.app_outcome(app_id, region).await
+    }
+
+    /// Price of `iap` in one storefront, matched by resource ID or product
+    /// identifier, or failing that by name or price tier with a
+    /// [`Pricing::match_note`] saying so. `known` is the listing `iap` came
+    /// from, e.g. [`in_app_purchases`](Self::in_app_purchases) in the base
+    /// region; the fuzzy matches never pick one of its other IAPs.
+    pub async fn iap_price(
+        &self,
+        app_id: &str,
+        region: &Region,
+        iap: &InAppPurchase,
+        known: &[InAppPurchase],
+    ) -> Result<RegionOutcome, RegionError> {
+        self.store.iap_outcome(app_id, region, iap, known).await
+    }
+
+    /// Prices of every top in-app purchase in one storefront, from a single
+    /// page fetch, with IAPs matched to `known` where possible. `Ok(None)`
+    /// when the app isn't sold there.
+    pub async fn all_iap_price(
+        &self,
+        app_id: &str,
+        region: &Region,
+        known: &[InAppPurchase],
+    ) -> Result<Option<Vec<Pricing>>, RegionError> {
+        self.store.all_iap_prices(app_id, region, known).await
+    }
+
+    /// Price of the app in every region, yielded as each storefront responds.
+    /// At most the configured number of storefronts are fetched at once.
+    pub fn app_prices<'a>(
+        &'a self,
+        app_id: &'a str,
+        regions: &'a [Region],
+    ) -> impl Stream<Item = (Region, Result<RegionOutcome, RegionError>)> + 'a {
+        stream::iter(regions)
+            .map(move |region| async move { (*region, self.app_price(app_id, region).await) })
+            .buffer_unordered(self.concurrency)
+    }
+
+    /// Price of `iap`, one of `known`, in every region, yielded as each
+    /// storefront responds. At most the configured number of storefronts are
+    /// fetched at once.
+    pub fn iap_prices<'a>(
+        &'a self,
+        app_id: &'a str,
+        iap: &'a InAppPurchase,
+        known: &'a [InAppPurchase],
+        regions: &'a [Region],
+    ) -> impl Stream<Item = (Region, Result<RegionOutcome, RegionError>)> + 'a {
+        stream::iter(regions)
+            .map(move |region| async move { (*region, self.iap_price(app_id, region, iap, known).await) })
+            .buffer_unordered(self.concurrency)
+    }
+
+    /// Prices of every top in-app purchase in every region, one page per
+    /// storefront, yielded as each storefront responds. Entries carry the
+    /// product identifier of the matching IAP in `known` (or their own) in
+    /// [`Pricing::product_id`].
+    pub fn all_iap_prices<'a>(
+        &'a self,
+        app_id: &'a str,
+        known: &'a [InAppPurchase],
+        regions: &'a [Region],
+    ) -> impl Stream<Item = (Region, Result<Option<Vec<Pricing>>, RegionError>)> + 'a {
+        stream::iter(regions)
+            .map(move |region| async move { (*region, self.all_iap_price(app_id, region, known).await) })
+            .buffer_unordered(self.concurrency)
+    }
+
+    /// Exchange rates from the configured provider, as units per one
+    /// `base_currency`. Pass them to [`ManualRates`](crate::fx::ManualRates)
+    /// to convert many lookups against the same table.
+    pub async fn rates(&self, base_currency: &str) -> Result<HashMap<String, f64>> {
+        self.fx.rates(self.store.transport.as_ref(), base_currency).await
+    }
+
+    /// Convert every entry to `base_currency` using the configured exchange
+    /// rate provider and sort cheapest first.
+    pub async fn convert(&self, pricing: &mut [Pricing], base_currency: &str) -> Result<()> {
+        let rates = self.rates(base_currency).await?;
+        convert_prices(pricing, base_currency, &rates);
+        Ok(())
+    }
+}
@@ -0,0 +1,183 @@
+//! Currency symbols and locale-ish price formatting.
+
+/// Map ISO currency code to symbol
+pub fn currency_symbol(code: &str) -> &str {
+    match code {
+        // Africa, Middle East, India
+        "DZD" => "دج",   // Algerian Dinar
+        "AOA" => "Kz",   // Angolan Kwanza
+        "XOF" => "Fr",   // CFA Franc BCEAO
+        "BWP" => "P",    // Botswana Pula
+        "XAF" => "Fr",   // CFA Franc BEAC
+        "CDF" => "FC",   // Congolese Franc
+        "EGP" => "£",    // Egyptian Pound
+        "GHS" => "₵",    // Ghanaian Cedi
+        "INR" => "₹",    // Indian Rupee
+        "ILS" => "₪",    // Israeli New Shekel
+        "JOD" => "JD",   // Jordanian Dinar
+        "KES" => "KSh",  // Kenyan Shilling
+        "KWD" => "KD",   // Kuwaiti Dinar
+        "LRD" => "$",    // Liberian Dollar
+        "LYD" => "LD",   // Libyan Dinar
+        "MGA" => "Ar",   // Malagasy Ariary
+        "MWK" => "MK",   // Malawian Kwacha
+        "MRU" => "UM",   // Mauritanian Ouguiya
+        "MUR" => "₨",    // Mauritian Rupee
+        "MAD" => "د.م.", // Moroccan Dirham
+        "MZN" => "MTn",  // Mozambican Metical
+        "NAD" => "$",    // Namibian Dollar
+        "NGN" => "₦",    // Nigerian Naira
+        "OMR" => "ر.ع.", // Omani Rial
+        "PKR" => "₨",    // Pakistani Rupee
+        "QAR" => "ر.ق",  // Qatari Riyal
+        "RWF" => "FRw",  // Rwandan Franc
+        "SAR" => "ر.س",  // Saudi Riyal
+        "SCR" => "SR",   // Seychellois Rupee
+        "SLL" => "Le",   // Sierra Leonean Leone
+        "ZAR" => "R",    // South African Rand
+        "TZS" => "TZS",  // Tanzanian Shilling
+        "TND" => "د.ت",  // Tunisian Dinar
+        "UGX" => "USh",  // Ugandan Shilling
+        "AED" => "د.إ",  // UAE Dirham
+        "ZMW" => "ZK",   // Zambian Kwacha
+        "ZWL" => "Z$",   // Zimbabwean Dollar
+        // Asia Pacific
+        "AUD" => "$",    // Australian Dollar
+        "BDT" => "৳",    // Bangladeshi Taka
+        "BTN" => "Nu.",  // Bhutanese Ngultrum
+        "BND" => "B$",   // Brunei Dollar
+        "KHR" => "៛",    // Cambodian Riel (suffix)
+        "CNY" => "¥",    // Chinese Yuan
+        "FJD" => "FJ$",  // Fiji Dollar
+        "HKD" => "HK$",  // Hong Kong Dollar
+        "IDR" => "Rp",   // Indonesian Rupiah
+        "JPY" => "¥",    // Japanese Yen
+        "KZT" => "₸",    // Kazakhstani Tenge
+        "KGS" => "лв",   // Kyrgyzstani Som
+        "MOP" => "P",    // Macanese Pataca
+        "MYR" => "RM",   // Malaysian Ringgit
+        "MVR" => "Rf.",  // Maldivian Rufiyaa
+        "MNT" => "₮",    // Mongolian Tögrög
+        "MMK" => "K",    // Myanmar Kyat (suffix)
+        "NPR" => "₨",    // Nepalese Rupee
+        "NZD" => "NZ$",  // New Zealand Dollar
+        "PHP" => "₱",    // Philippine Peso
+        "SGD" => "S$",   // Singapore Dollar
+        "KRW" => "₩",    // South Korean Won
+        "LKR" => "Rs",   // Sri Lankan Rupee
+        "TWD" => "NT$",  // Taiwan Dollar
+        "TJS" => "TJS",  // Tajikistani Somoni
+        "THB" => "฿",    // Thai Baht
+        "TMT" => "m",    // Turkmenistani Manat
+        "UZS" => "so'm", // Uzbekistani so'm
+        "VND" => "₫",    // Vietnamese Dong (suffix)
+        // Europe
+        "ALL" => "L",       // Albanian Lek (suffix)
+        "AMD" => "AMD",     // Armenian Dram
+        "EUR" => "€",       // Euro
+        "AZN" => "₼",       // Azerbaijani Manat
+        "BYN" => "Br",      // Belarusian Ruble (suffix)
+        "BAM" => "KM",      // Bosnia Convertible Mark
+        "BGN" => "лв",      // Bulgarian Lev
+        "HRK" => "kn",      // Croatian Kuna
+        "CZK" => "Kč",      // Czech Koruna
+        "DKK" => "kr",      // Danish Krone
+        "GEL" => "₾",       // Georgian Lari
+        "HUF" => "HUF",     // Hungarian Forint (suffix; ISO)
+        "ISK" => "kr",      // Icelandic Króna
+        "MDL" => "L",       // Moldovan Leu (suffix)
+        "MKD" => "ден",     // North Macedonian Denar (suffix)
+        "NOK" => "kr",      // Norwegian Krone
+        "PLN" => "zł",      // Polish Zloty
+        "RON" => "lei",     // Romanian leu (suffix, decimals)
+        "RUB" => "₽",       // Russian Ruble
+        "SEK" => "kr",      // Swedish Krona
+        "CHF" => "Fr.",     // Swiss Franc
+        "TRY" => "₺",       // Turkish Lira
+        "UAH" => "₴",       // Ukrainian Hryvnia
+        "GBP" => "£",       // British Pound
+        // Latin America / Caribbean
+        "XCD" => "EC$",     // Eastern Caribbean Dollar
+        "ARS" => "$",       // Argentine Peso
+        "BSD" => "B$",      // Bahamian Dollar
+        "BBD" => "Bds$",    // Barbadian Dollar
+        "BZD" => "BZ$",     // Belize Dollar
+        "BOB" => "Bs.",     // Bolivian Boliviano
+        "BRL" => "R$",      // Brazilian Real
+        "KYD" => "CI$",     // Cayman Islands Dollar
+        "CLP" => "$",       // Chilean Peso
+        "COP" => "$",       // Colombian Peso
+        "CRC" => "₡",       // Costa Rican Colón
+        "DOP" => "RD$",     // Dominican Peso
+        "GTQ" => "Q",       // Guatemalan Quetzal
+        "GYD" => "G$",      // Guyanese Dollar
+        "HNL" => "L",       // Honduran Lempira
+        "JMD" => "J$",      // Jamaican Dollar
+        "MXN" => "$",       // Mexican Peso
+        "NIO" => "C$",      // Nicaraguan Córdoba
+        "PAB" => "B/.",     // Panamanian Balboa
+        "PYG" => "₲",       // Paraguayan guaraní
+        "PEN" => "S/.",     // Peruvian Sol
+        "SRD" => "$",       // Surinamese dollar
+        "TTD" => "TT$",     // Trinidad and Tobago Dollar
+        "UYU" => "$",       // Uruguayan peso
+        "VES" => "Bs.S",    // Venezuelan Bolívar Soberano
+        // North America
+        "CAD" => "$",       // Canadian Dollar
+        "USD" => "$",       // US Dollar
+        // Oceania
+        "PGK" => "K",       // Papua New Guinea Kina
+        "SBD" => "SI$",     // Solomon Islands dollar
+        "TOP" => "T$",      // Tongan paʻanga (suffix)
+        "VUV" => "VT",      // Vanuatu vatu (suffix)
+        _ => "",
+    }
+}
+
+/// Whether the symbol goes after the amount (`9 900 Fr`) rather than before (`$9.99`).
+pub fn currency_is_suffix(code: &str) -> bool {
+    matches!(
+        code,
+        // Africa (suffix)
+        "DZD" | "AOA" | "BWP" | "GHS" | "KES" | "LSL" | "LYD"
+        | "MGA" | "MWK" | "MUR" | "MZN" | "NAD" | "NGN" | "RWF" | "SCR" | "SLL" | "SZL"
+        | "TZS" | "UGX" | "XAF" | "XOF" | "ZAR" | "ZMW" | "ZWL" | "KMF" | "CFA" | "CDF"
+        // Asia
+        | "KHR" | "MMK" | "VND"
+        // Oceania
+        | "VUV" | "TOP"
+        // Europe (suffix)
+        | "ALL" | "MKD" | "MDL" | "RON" | "RSD" | "UAH" | "HUF" | "BYN"
+    )
+}
+
+/// Format the numeric part with the number of decimals the currency normally uses.
+pub fn format_amount(amount: f64, code: &str) -> String {
+    match code {
+        // No decimals for these
+        "JPY" | "KRW" | "VND" | "IDR" | "MMK" | "LAK" | "KHR" | "UGX" | "TZS" | "MWK" | "MGA"
+        | "CDF" | "RWF" | "GNF" | "XOF" | "XAF" | "KMF" | "MZN" | "BIF" | "VUV" | "SLL" | "BYN"
+            => format!("{:.0}", amount),
+        // 3 decimals for some Gulf/Arab currencies
+        "KWD" | "BHD" | "IQD" | "OMR" | "TND" | "LYD" | "JOD" => format!("{:.3}", amount),
+        _ => format!("{:.2}", amount),
+    }
+}
+
+/// Format an amount with its currency symbol, e.g. `$9.99`, `€4.49`, `1200 Fr`.
+pub fn format_price(amount: f64, code: &str) -> String {
+    let symbol = currency_symbol(code);
+    if currency_is_suffix(code) {
+        if !symbol.is_empty() {
+            format!("{} {}", format_amount(amount, code), symbol)
+        } else {
+            format!("{} {}", format_amount(amount, code), code)
+        }
+    } else {
+        if !symbol.is_empty() {
+            format!("{}{}", symbol, format_amount(amount, code))
+        } else {
+            format!("{} {}", code, format_amount(amount, code))
+        }
+    }
+}
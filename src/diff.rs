@@ -0,0 +1,101 @@
+//! Comparing two sets of prices region by region, e.g. a saved snapshot
+//! against a fresh fetch, to spot Apple's tax and tier adjustments.
+
+use std::collections::HashMap;
+use serde::Serialize;
+use crate::pricing::Pricing;
+
+/// Amounts closer than this count as the same price.
+const EPSILON: f64 = 1e-9;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ChangeKind {
+    /// Priced now but not before.
+    Added,
+    /// Priced before but not now.
+    Removed,
+    /// Priced both times, at a different amount or in a different currency.
+    Changed,
+}
+
+/// How one product's price in one storefront moved.
+///
+/// Field names double as the column names of the machine-readable outputs,
+/// as with [`Pricing`].
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct PriceChange {
+    pub region_code: String,
+    pub region_name: String,
+    /// Product identifier of the in-app purchase; `None` for the app itself.
+    pub product_id: Option<String>,
+    pub change: ChangeKind,
+    pub old_amount: Option<f64>,
+    pub old_currency: Option<String>,
+    pub new_amount: Option<f64>,
+    pub new_currency: Option<String>,
+    /// Change in the local price, in percent; `None` unless both prices are
+    /// in the same currency.
+    pub amount_change_pct: Option<f64>,
+    pub old_converted: Option<f64>,
+    pub new_converted: Option<f64>,
+    /// Change in the base-currency price, in percent. This includes any
+    /// movement in exchange rates between the two fetches.
+    pub converted_change_pct: Option<f64>,
+}
+
+impl PriceChange {
+    fn new(before: Option<&Pricing>, after: Option<&Pricing>, change: ChangeKind) -> Self {
+        let either = after.or(before).expect("a change has a price on at least one side");
+        let same_currency = matches!((before, after), (Some(b), Some(a)) if a.currency == b.currency);
+        let (old_amount, new_amount) = (before.map(|p| p.amount), after.map(|p| p.amount));
+        let old_converted = before.and_then(|p| p.converted_amount);
+        let new_converted = after.and_then(|p| p.converted_amount);
+        PriceChange {
+            region_code: either.region_code.clone(),
+            region_name: either.region_name.clone(),
+            product_id: either.product_id.clone(),
+            change,
+            old_amount,
+            old_currency: before.map(|p| p.currency.clone()),
+            new_amount,
+            new_currency: after.map(|p| p.currency.clone()),
+            amount_change_pct: if same_currency { percent(old_amount, new_amount) } else { None },
+            old_converted,
+            new_converted,
+            converted_change_pct: percent(old_converted, new_converted),
+        }
+    }
+}
+
+fn percent(old: Option<f64>, new: Option<f64>) -> Option<f64> {
+    let (old, new) = (old?, new?);
+    (old.abs() > EPSILON).then(|| (new - old) / old * 100.0)
+}
+
+/// Regions and products whose price differs between `before` and `after`,
+/// matched on product ID and region code. Changes come in `after` order,
+/// followed by whatever was removed, in `before` order.
+pub fn diff_prices(before: &[Pricing], after: &[Pricing]) -> Vec<PriceChange> {
+    let key = |p: &Pricing| (p.product_id.clone(), p.region_code.clone());
+    let old: HashMap<_, &Pricing> = before.iter().map(|p| (key(p), p)).collect();
+    let new: HashMap<_, &Pricing> = after.iter().map(|p| (key(p), p)).collect();
+
+    let mut changes: Vec<PriceChange> = after
+        .iter()
+        .filter_map(|a| match old.get(&key(a)) {
+            None => Some(PriceChange::new(None, Some(a), ChangeKind::Added)),
+            Some(b) if b.currency != a.currency || (b.amount - a.amount).abs() > EPSILON => {
+                Some(PriceChange::new(Some(b), Some(a), ChangeKind::Changed))
+            }
+            Some(_) => None,
+        })
+        .collect();
+    changes.extend(
+        before
+            .iter()
+            .filter(|b| !new.contains_key(&key(b)))
+            .map(|b| PriceChange::new(Some(b), None, ChangeKind::Removed)),
+    );
+    changes
+}
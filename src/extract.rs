@@ -0,0 +1,339 @@
+//! Price extraction from storefront pages.
+//!
+//! Nothing here does I/O: every function works on an already fetched [`Page`],
+//! so the same code runs against live responses and saved fixtures.
+
+use std::cmp::Ordering;
+use regex::Regex;
+use serde_json::Value;
+use crate::pricing::{InAppPurchase, Pricing, RegionError, RegionOutcome};
+use crate::regions::Region;
+use crate::shoebox::{parse_app_resource, parse_relationship_page, InAppResource};
+
+/// Case-insensitive name similarity (normalized Levenshtein) from which a
+/// renamed IAP is taken to be the same product.
+const NAME_SIMILARITY: f64 = 0.8;
+
+/// An `apps.apple.com` app page as it came back from the server.
+#[derive(Debug, Clone)]
+pub struct Page {
+    pub status: u16,
+    pub body: String,
+}
+
+impl Page {
+    pub fn new(status: u16, body: impl Into<String>) -> Self {
+        Page { status, body: body.into() }
+    }
+
+    /// Apple answers 404 for apps that aren't sold in the storefront.
+    pub fn is_not_available(&self) -> bool {
+        self.status == 404
+    }
+}
+
+/// The app's display name from the page's `og:title`.
+pub fn app_name(page: &Page) -> Option<String> {
+    if page.is_not_available() {
+        return None;
+    }
+    let re = Regex::new(r#"<meta property="og:title" content="([^"]+)""#).unwrap();
+    let caps = re.captures(&page.body)?;
+    Some(html_escape::decode_html_entities(&caps[1]).into_owned())
+}
+
+/// The page's canonical App Store URL, e.g. where an `apple.co` link landed.
+pub fn canonical_url(page: &Page) -> Option<String> {
+    let re = Regex::new(r#"<link rel="canonical" href="([^"]+)""#).unwrap();
+    let re_og = Regex::new(r#"<meta property="og:url" content="([^"]+)""#).unwrap();
+    let caps = re.captures(&page.body).or_else(|| re_og.captures(&page.body))?;
+    Some(html_escape::decode_html_entities(&caps[1]).into_owned())
+}
+
+/// The catalog-API bearer token the page's web app is configured with, from
+/// the URL-encoded JSON in its `web-experience-app/config/environment` meta
+/// tag.
+pub fn media_api_token(page: &Page) -> Option<String> {
+    let re = Regex::new(r#"<meta name="web-experience-app/config/environment" content="([^"]+)""#).unwrap();
+    let caps = re.captures(&page.body)?;
+    let content = html_escape::decode_html_entities(&caps[1]).into_owned();
+    let config = percent_encoding::percent_decode_str(&content).decode_utf8().ok()?;
+    let config: Value = serde_json::from_str(&config).ok()?;
+    Some(config.pointer("/MEDIA_API/token")?.as_str()?.to_string())
+}
+
+/// The page's "top in-app purchases". `Ok(None)` when the app isn't sold here.
+pub fn in_app_purchases(page: &Page) -> Result<Option<Vec<InAppPurchase>>, RegionError> {
+    Ok(iap_listing(page)?.map(|listing| listing.purchases()))
+}
+
+/// The in-app purchases a storefront lists for an app: the page's
+/// `top-in-apps` shortlist, optionally extended with further pages from
+/// Apple's catalog API.
+#[derive(Debug, Clone)]
+pub struct IapListing {
+    items: Vec<InAppResource>,
+    /// Catalog-API path of the next page, while Apple has more to list.
+    pub next: Option<String>,
+    /// Bearer token for the catalog API, from the storefront page.
+    pub token: Option<String>,
+    /// Why further pages weren't fetched, when they were tried.
+    pub truncated: Option<String>,
+}
+
+/// The page's IAP listing. `Ok(None)` when the app isn't sold here.
+pub fn iap_listing(page: &Page) -> Result<Option<IapListing>, RegionError> {
+    if page.is_not_available() {
+        return Ok(None);
+    }
+    let app = parse_app_resource(&page.body)?;
+    Ok(Some(IapListing {
+        items: app.top_in_apps().to_vec(),
+        next: app.top_in_apps_next().map(str::to_string),
+        token: media_api_token(page),
+        truncated: None,
+    }))
+}
+
+impl IapListing {
+    /// Whether every IAP Apple lists for the app is included.
+    pub fn is_complete(&self) -> bool {
+        self.next.is_none()
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Append a page fetched from [`IapListing::next`] and move `next` on.
+    pub fn extend_from_page(&mut self, body: &str) -> Result<(), RegionError> {
+        let page = parse_relationship_page(body)?;
+        self.items.extend(page.data);
+        self.next = page.next;
+        Ok(())
+    }
+
+    /// The listed IAPs, with their price tiers filled in.
+    pub fn purchases(&self) -> Vec<InAppPurchase> {
+        self.items
+            .iter()
+            .zip(price_tiers(&self.items))
+            .map(|(item, tier)| InAppPurchase { price_tier: tier, ..InAppPurchase::from(item) })
+            .collect()
+    }
+
+    /// Price of `selected` in this listing, matched by resource ID or product
+    /// identifier, or failing that by name or price tier with a
+    /// [`Pricing::match_note`] saying so. `known` is the listing `selected`
+    /// was picked from, so items that are recognisably one of its other IAPs
+    /// are never taken for `selected`.
+    pub fn price_of(
+        &self,
+        region: &Region,
+        selected: &InAppPurchase,
+        known: &[InAppPurchase],
+    ) -> Result<RegionOutcome, RegionError> {
+        let mut known = known.to_vec();
+        let k = known.iter().position(|k| k.id == selected.id).unwrap_or_else(|| {
+            known.push(selected.clone());
+            known.len() - 1
+        });
+        match match_iaps(&known, &self.items).swap_remove(k) {
+            Some(found) => Ok(RegionOutcome::Found(self.matched_price(region, selected, found)?)),
+            None => Ok(RegionOutcome::NoPrice),
+        }
+    }
+
+    /// Prices of every listed IAP. IAPs matching one of `known` come first,
+    /// in `known` order and keyed by its product identifier; any others
+    /// follow in listing order.
+    pub fn prices(&self, region: &Region, known: &[InAppPurchase]) -> Result<Vec<Pricing>, RegionError> {
+        let matches = match_iaps(known, &self.items);
+        let mut prices = Vec::new();
+        for (iap, found) in known.iter().zip(matches.iter().cloned()) {
+            if let Some(found) = found {
+                prices.push(self.matched_price(region, iap, found)?);
+            }
+        }
+        for (i, item) in self.items.iter().enumerate() {
+            if !matches.iter().flatten().any(|(j, _)| *j == i) {
+                prices.push(self.flag(Pricing::for_offer(region, item)?));
+            }
+        }
+        Ok(prices)
+    }
+
+    /// Price of `known`'s IAP found at `items[index]`, keyed by `known`'s product identifier.
+    fn matched_price(
+        &self,
+        region: &Region,
+        known: &InAppPurchase,
+        (index, note): (usize, Option<String>),
+    ) -> Result<Pricing, RegionError> {
+        let mut pricing = Pricing::for_offer(region, &self.items[index])?.for_product(&known.offer_name);
+        pricing.match_note = note;
+        Ok(self.flag(pricing))
+    }
+
+    fn flag(&self, mut pricing: Pricing) -> Pricing {
+        pricing.catalog_complete = Some(self.is_complete());
+        pricing
+    }
+}
+
+/// Rank of each item by price, cheapest first and ties in listing order;
+/// `None` for items without an offer.
+fn price_tiers(items: &[InAppResource]) -> Vec<Option<usize>> {
+    let mut by_price: Vec<(usize, f64)> = items
+        .iter()
+        .enumerate()
+        .filter_map(|(i, item)| item.attributes.offers.first().map(|o| (i, o.price)))
+        .collect();
+    by_price.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal));
+    let mut tiers = vec![None; items.len()];
+    for (tier, (i, _)) in by_price.into_iter().enumerate() {
+        tiers[i] = Some(tier);
+    }
+    tiers
+}
+
+/// Find each of `known` among `items`: the index of the matching item and,
+/// for fuzzy matches, a note on how it was found.
+///
+/// Apple's resource ID and the developer's product identifier are stable
+/// across storefronts and tried first. Whatever is left is paired by name
+/// similarity and then by price-tier position, among items nothing else
+/// has claimed.
+fn match_iaps(known: &[InAppPurchase], items: &[InAppResource]) -> Vec<Option<(usize, Option<String>)>> {
+    let mut matches: Vec<Option<(usize, Option<String>)>> = known
+        .iter()
+        .map(|k| {
+            items
+                .iter()
+                .position(|item| item.id == k.id)
+                .or_else(|| items.iter().position(|item| item.attributes.offer_name == k.offer_name))
+                .map(|i| (i, None))
+        })
+        .collect();
+    let claimed = |matches: &[Option<(usize, Option<String>)>], i: usize| {
+        matches.iter().flatten().any(|(j, _)| *j == i)
+    };
+
+    for (k, iap) in known.iter().enumerate() {
+        if matches[k].is_some() {
+            continue;
+        }
+        let name = iap.name.to_lowercase();
+        let best = items
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !claimed(&matches, *i))
+            .map(|(i, item)| (i, strsim::normalized_levenshtein(&name, &item.attributes.name.to_lowercase())))
+            .filter(|(_, score)| *score >= NAME_SIMILARITY)
+            .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal));
+        if let Some((i, _)) = best {
+            let note = format!("matched by name similarity to \"{}\"", items[i].attributes.name);
+            matches[k] = Some((i, Some(note)));
+        }
+    }
+
+    let tiers = price_tiers(items);
+    for (k, iap) in known.iter().enumerate() {
+        let Some(tier) = iap.price_tier.filter(|_| matches[k].is_none()) else {
+            continue;
+        };
+        if let Some(i) = (0..items.len()).find(|&i| tiers[i] == Some(tier) && !claimed(&matches, i)) {
+            let note = format!("matched by price tier #{} (\"{}\")", tier + 1, items[i].attributes.name);
+            matches[k] = Some((i, Some(note)));
+        }
+    }
+    matches
+}
+
+/// Price of `selected` on this page, from the `top-in-apps` shortlist; see
+/// [`IapListing::price_of`].
+pub fn iap_outcome(
+    page: &Page,
+    region: &Region,
+    selected: &InAppPurchase,
+    known: &[InAppPurchase],
+) -> Result<RegionOutcome, RegionError> {
+    match iap_listing(page)? {
+        Some(listing) => listing.price_of(region, selected, known),
+        None => Ok(RegionOutcome::NotAvailable),
+    }
+}
+
+/// Prices of every IAP in this page's `top-in-apps` shortlist; see
+/// [`IapListing::prices`]. `Ok(None)` when the app isn't sold here.
+pub fn all_iap_prices(
+    page: &Page,
+    region: &Region,
+    known: &[InAppPurchase],
+) -> Result<Option<Vec<Pricing>>, RegionError> {
+    iap_listing(page)?.map(|listing| listing.prices(region, known)).transpose()
+}
+
+/// Price of the app itself, trying the shoebox JSON first and then each of
+/// the HTML fallbacks Apple has used over time.
+pub fn app_outcome(page: &Page, region: &Region) -> Result<RegionOutcome, RegionError> {
+    if page.is_not_available() {
+        return Ok(RegionOutcome::NotAvailable);
+    }
+    let html = &page.body;
+    let found = |amount: f64, curr: &str| RegionOutcome::Found(Pricing::new(region, amount, curr));
+
+    // Try via shoebox JSON:
+    let shoebox = parse_app_resource(html);
+    if let Ok(app) = &shoebox {
+        let attr = &app.attributes;
+        if let (Some(amount), Some(curr), Some(_label)) =
+            (attr.price, attr.currency_code.as_deref(), attr.formatted_price.as_deref())
+        {
+            return Ok(found(amount, curr));
+        }
+    }
+
+    // HTML fallback:
+
+    // 1) Try Open Graph price:
+    let re_og_amt = Regex::new(r#"<meta property="og:price:amount" content="([^"]+)""#).unwrap();
+    let re_og_cur = Regex::new(r#"<meta property="og:price:currency" content="([^"]+)""#).unwrap();
+    if let (Some(am), Some(cur)) = (re_og_amt.captures(html), re_og_cur.captures(html)) {
+        // An unparseable amount falls through to the next strategy rather than reading as 0.
+        if let Ok(amount) = am[1].parse::<f64>() {
+            return Ok(found(amount, &cur[1]));
+        }
+    }
+
+    // 2) Try JSON-LD:
+    let re_ld = Regex::new(r#"<script[^>]*type="application/ld\+json"[^>]*>([\s\S]*?)</script>"#).unwrap();
+    if let Some(c) = re_ld.captures(html) {
+        let blob = c[1].trim();
+        if let Ok(val) = serde_json::from_str::<Value>(blob) {
+            if let Some(off) = val.get("offers") {
+                let amount = off.get("price").and_then(|v| v.as_f64());
+                let curr = off.get("priceCurrency").and_then(|v| v.as_str());
+                if let (Some(amount), Some(curr)) = (amount, curr) {
+                    return Ok(found(amount, curr));
+                }
+            }
+        }
+    }
+
+    // 3) HTML <li> legacy fallback:
+    let re_html = Regex::new(
+        r#"<li[^>]*class="inline-list__item[^"]*app-header__list__item--price"[^>]*>([^<]+)</li>"#
+    ).unwrap();
+    if let Some(cap) = re_html.captures(html) {
+        let raw = cap[1].replace("&nbsp;", " ").trim().to_string();
+        return Ok(RegionOutcome::Label(raw));
+    }
+
+    // Nothing matched: only blame the page if not even the shoebox was readable.
+    shoebox.map(|_| RegionOutcome::NoPrice)
+}
@@ -0,0 +1,210 @@
+//! Exchange rates, from whichever source the prices should be booked against.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::PathBuf;
+use anyhow::{anyhow, bail, Context, Result};
+use async_trait::async_trait;
+use regex::Regex;
+use serde_json::Value;
+use crate::transport::Transport;
+
+/// open.er-api.com's latest-rates endpoint; the base currency is appended.
+pub const DEFAULT_FX_URL: &str = "https://open.er-api.com/v6/latest";
+
+/// The European Central Bank's daily euro reference rates.
+pub const DEFAULT_ECB_URL: &str = "https://www.ecb.europa.eu/stats/eurofxref/eurofxref-daily.xml";
+
+/// A source of exchange rates. Requests go through the client's
+/// [`Transport`], so they share its rate limiting, retries and proxy.
+#[async_trait]
+pub trait FxProvider: fmt::Debug + Send + Sync {
+    /// Rates keyed by currency code, as units per one `base`.
+    async fn rates(&self, transport: &dyn Transport, base: &str) -> Result<HashMap<String, f64>>;
+}
+
+/// Fetch exchange rates from an open.er-api.com-compatible `endpoint`, keyed
+/// by currency code and expressed as units per one `base`.
+pub async fn fetch_conversion_rates(
+    transport: &dyn Transport,
+    endpoint: &str,
+    base: &str,
+) -> Result<HashMap<String, f64>> {
+    let url = format!("{}/{}", endpoint.trim_end_matches('/'), base);
+    let res = transport.get(&url).await?;
+    if !res.is_success() {
+        bail!("Exchange rate request failed with HTTP {}", res.status);
+    }
+    let res: Value = serde_json::from_str(&res.body).context("Invalid exchange rate response")?;
+    let rates = res["rates"]
+        .as_object()
+        .context("Missing exchange rates in response")?
+        .iter()
+        .filter_map(|(k, v)| v.as_f64().map(|f| (k.clone(), f)))
+        .collect();
+    Ok(rates)
+}
+
+/// `rate` for `code`, if it's usable as an exchange rate: finite and above
+/// zero.
+pub fn check_rate(code: &str, rate: f64) -> Result<f64> {
+    if rate.is_finite() && rate > 0.0 {
+        Ok(rate)
+    } else {
+        bail!("Rate for {} must be a positive number, not {}", code, rate)
+    }
+}
+
+/// Restate `rates`, given per one `from`, as units per one `to`.
+pub fn rebase(rates: &HashMap<String, f64>, from: &str, to: &str) -> Result<HashMap<String, f64>> {
+    for (code, rate) in rates {
+        check_rate(code, *rate)?;
+    }
+    let mut rates = rates.clone();
+    rates.entry(from.to_string()).or_insert(1.0);
+    let pivot = *rates
+        .get(to)
+        .filter(|r| **r > 0.0)
+        .ok_or_else(|| anyhow!("No {} rate available to convert from {}", to, from))?;
+    Ok(rates.into_iter().map(|(code, rate)| (code, rate / pivot)).collect())
+}
+
+/// Live rates from open.er-api.com or a compatible endpoint.
+#[derive(Debug, Clone)]
+pub struct OpenErApi {
+    pub url: String,
+}
+
+impl Default for OpenErApi {
+    fn default() -> Self {
+        OpenErApi { url: DEFAULT_FX_URL.to_string() }
+    }
+}
+
+#[async_trait]
+impl FxProvider for OpenErApi {
+    async fn rates(&self, transport: &dyn Transport, base: &str) -> Result<HashMap<String, f64>> {
+        fetch_conversion_rates(transport, &self.url, base).await
+    }
+}
+
+/// The ECB's daily reference rates, published against the euro and
+/// rebased to the requested currency.
+#[derive(Debug, Clone)]
+pub struct Ecb {
+    pub url: String,
+}
+
+impl Default for Ecb {
+    fn default() -> Self {
+        Ecb { url: DEFAULT_ECB_URL.to_string() }
+    }
+}
+
+/// Euro rates from an `eurofxref` XML document.
+pub fn parse_ecb_rates(xml: &str) -> Result<HashMap<String, f64>> {
+    let re = Regex::new(r#"<Cube\s+currency=['"]([A-Z]{3})['"]\s+rate=['"]([0-9.]+)['"]"#).unwrap();
+    let rates: HashMap<String, f64> = re
+        .captures_iter(xml)
+        .filter_map(|c| Some((c[1].to_string(), c[2].parse().ok()?)))
+        .collect();
+    if rates.is_empty() {
+        bail!("No rates found in ECB reference feed");
+    }
+    Ok(rates)
+}
+
+#[async_trait]
+impl FxProvider for Ecb {
+    async fn rates(&self, transport: &dyn Transport, base: &str) -> Result<HashMap<String, f64>> {
+        let res = transport.get(&self.url).await?;
+        if !res.is_success() {
+            bail!("ECB reference rate request failed with HTTP {}", res.status);
+        }
+        rebase(&parse_ecb_rates(&res.body)?, "EUR", base)
+    }
+}
+
+/// Rates read from a local file, e.g. the table finance books against.
+///
+/// JSON files use the open.er-api.com shape, `{"base": "EUR", "rates":
+/// {"USD": 1.08, ...}}` (`base_code` is accepted too). CSV files need a
+/// `base,currency,rate` header and a single base currency.
+#[derive(Debug, Clone)]
+pub struct RatesFile {
+    pub path: PathBuf,
+}
+
+/// Rates and their base currency from a JSON or CSV rates file's contents.
+pub fn parse_rates_file(contents: &str) -> Result<(String, HashMap<String, f64>)> {
+    if contents.trim_start().starts_with('{') {
+        let doc: Value = serde_json::from_str(contents).context("Invalid JSON rates file")?;
+        let base = doc["base"]
+            .as_str()
+            .or_else(|| doc["base_code"].as_str())
+            .context("JSON rates file has no `base` currency")?;
+        let rates = doc["rates"]
+            .as_object()
+            .context("JSON rates file has no `rates` object")?
+            .iter()
+            .map(|(k, v)| {
+                let rate = v.as_f64().with_context(|| format!("Rate for {} is not a number", k))?;
+                Ok((k.clone(), check_rate(k, rate)?))
+            })
+            .collect::<Result<_>>()?;
+        return Ok((base.to_uppercase(), rates));
+    }
+
+    let mut lines = contents.lines().map(str::trim).filter(|l| !l.is_empty() && !l.starts_with('#'));
+    let header: Vec<String> = lines
+        .next()
+        .context("Empty rates file")?
+        .split(',')
+        .map(|h| h.trim().to_ascii_lowercase())
+        .collect();
+    let column = |name: &str| {
+        header.iter().position(|h| h == name).with_context(|| format!("CSV rates file has no `{}` column", name))
+    };
+    let (base_col, currency_col, rate_col) = (column("base")?, column("currency")?, column("rate")?);
+    let mut base: Option<String> = None;
+    let mut rates = HashMap::new();
+    for (n, line) in lines.enumerate() {
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        let field = |i: usize| fields.get(i).copied().with_context(|| format!("Row {} is missing a column", n + 2));
+        let row_base = field(base_col)?.to_uppercase();
+        match &base {
+            Some(b) if *b != row_base => bail!("Rates file mixes base currencies {} and {}", b, row_base),
+            _ => base = Some(row_base),
+        }
+        let currency = field(currency_col)?.to_uppercase();
+        let rate: f64 = field(rate_col)?.parse().with_context(|| format!("Row {} has an invalid rate", n + 2))?;
+        let rate = check_rate(&currency, rate).with_context(|| format!("Row {} has an invalid rate", n + 2))?;
+        rates.insert(currency, rate);
+    }
+    Ok((base.context("Rates file has no rates")?, rates))
+}
+
+#[async_trait]
+impl FxProvider for RatesFile {
+    async fn rates(&self, _transport: &dyn Transport, base: &str) -> Result<HashMap<String, f64>> {
+        let contents = std::fs::read_to_string(&self.path)
+            .with_context(|| format!("Unable to read rates file {}", self.path.display()))?;
+        let (from, rates) = parse_rates_file(&contents)
+            .with_context(|| format!("Invalid rates file {}", self.path.display()))?;
+        rebase(&rates, &from, base)
+    }
+}
+
+/// A fixed table of rates, as units per one `base`.
+#[derive(Debug, Clone)]
+pub struct ManualRates {
+    pub base: String,
+    pub rates: HashMap<String, f64>,
+}
+
+#[async_trait]
+impl FxProvider for ManualRates {
+    async fn rates(&self, _transport: &dyn Transport, base: &str) -> Result<HashMap<String, f64>> {
+        rebase(&self.rates, &self.base, base)
+    }
+}
@@ -0,0 +1,195 @@
+//! Every run's prices, kept in a local SQLite database so past snapshots can
+//! be listed and shown again.
+
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::Serialize;
+use crate::pricing::Pricing;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS snapshots (
+    id            INTEGER PRIMARY KEY,
+    app_id        TEXT NOT NULL,
+    app_name      TEXT,
+    base_currency TEXT NOT NULL,
+    taken_at      TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS prices (
+    snapshot_id      INTEGER NOT NULL REFERENCES snapshots(id),
+    iap_key          TEXT NOT NULL,
+    region_code      TEXT NOT NULL,
+    region_name      TEXT NOT NULL,
+    amount           REAL NOT NULL,
+    currency         TEXT NOT NULL,
+    converted_amount REAL,
+    rate             REAL,
+    fetched_at       TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS snapshots_by_app ON snapshots(app_id, taken_at);
+CREATE INDEX IF NOT EXISTS prices_by_snapshot ON prices(snapshot_id, iap_key);
+";
+
+/// One run's worth of prices for an app.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    pub id: i64,
+    pub app_id: String,
+    pub app_name: Option<String>,
+    pub base_currency: String,
+    pub taken_at: DateTime<Utc>,
+    /// Product identifiers priced, in the order first seen; empty for the
+    /// app itself.
+    pub iap_keys: Vec<String>,
+    /// Storefronts with at least one price.
+    pub regions: usize,
+}
+
+/// A price history database.
+pub struct History {
+    conn: Connection,
+}
+
+impl History {
+    /// Open or create the database at `path`, creating its directory too.
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Unable to create history directory {}", dir.display()))?;
+        }
+        let conn = Connection::open(path)
+            .with_context(|| format!("Unable to open history database {}", path.display()))?;
+        conn.execute_batch(SCHEMA).context("Unable to set up history database")?;
+        Ok(History { conn })
+    }
+
+    /// `$XDG_DATA_HOME/appstore_pricing/history.sqlite3`, or the platform's
+    /// equivalent.
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::data_dir().map(|d| d.join("appstore_pricing").join("history.sqlite3"))
+    }
+
+    /// Store `pricing` as a new snapshot of `app_id` and return its ID.
+    pub fn record(
+        &mut self,
+        app_id: &str,
+        app_name: Option<&str>,
+        base_currency: &str,
+        pricing: &[Pricing],
+    ) -> Result<i64> {
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "INSERT INTO snapshots (app_id, app_name, base_currency, taken_at) VALUES (?1, ?2, ?3, ?4)",
+            params![app_id, app_name, base_currency, Utc::now()],
+        )?;
+        let id = tx.last_insert_rowid();
+        {
+            let mut insert = tx.prepare(
+                "INSERT INTO prices (snapshot_id, iap_key, region_code, region_name, amount, currency,
+                                     converted_amount, rate, fetched_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            )?;
+            for p in pricing {
+                insert.execute(params![
+                    id,
+                    p.product_id.as_deref().unwrap_or_default(),
+                    p.region_code,
+                    p.region_name,
+                    p.amount,
+                    p.currency,
+                    p.converted_amount,
+                    p.rate,
+                    p.fetched_at,
+                ])?;
+            }
+        }
+        tx.commit()?;
+        Ok(id)
+    }
+
+    /// Snapshots, newest first, optionally only those of `app_id` or that
+    /// priced the in-app purchase `iap`.
+    pub fn snapshots(&self, app_id: Option<&str>, iap: Option<&str>, limit: usize) -> Result<Vec<Snapshot>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, app_id, app_name, base_currency, taken_at FROM snapshots s
+             WHERE (?1 IS NULL OR app_id = ?1)
+               AND (?2 IS NULL OR EXISTS (SELECT 1 FROM prices WHERE snapshot_id = s.id AND iap_key = ?2))
+             ORDER BY taken_at DESC, id DESC
+             LIMIT ?3",
+        )?;
+        let limit = i64::try_from(limit).unwrap_or(i64::MAX);
+        let heads = stmt
+            .query_map(params![app_id, iap, limit], snapshot_head)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        heads.into_iter().map(|s| self.fill(s)).collect()
+    }
+
+    /// The snapshot with this ID.
+    pub fn snapshot(&self, id: i64) -> Result<Option<Snapshot>> {
+        let head = self
+            .conn
+            .query_row(
+                "SELECT id, app_id, app_name, base_currency, taken_at FROM snapshots WHERE id = ?1",
+                [id],
+                snapshot_head,
+            )
+            .optional()?;
+        head.map(|s| self.fill(s)).transpose()
+    }
+
+    /// The newest snapshot of `app_id`, or of `iap` within it.
+    pub fn latest(&self, app_id: &str, iap: Option<&str>) -> Result<Option<Snapshot>> {
+        Ok(self.snapshots(Some(app_id), iap, 1)?.into_iter().next())
+    }
+
+    /// Prices recorded in snapshot `id`, optionally only those of `iap`, as
+    /// they were after conversion.
+    pub fn prices(&self, id: i64, iap: Option<&str>) -> Result<Vec<Pricing>> {
+        let base: String = self.conn.query_row("SELECT base_currency FROM snapshots WHERE id = ?1", [id], |r| r.get(0))?;
+        let mut stmt = self.conn.prepare(
+            "SELECT iap_key, region_code, region_name, amount, currency, converted_amount, rate, fetched_at
+             FROM prices WHERE snapshot_id = ?1 AND (?2 IS NULL OR iap_key = ?2)
+             ORDER BY rowid",
+        )?;
+        let rows = stmt.query_map(params![id, iap], |r| {
+            let iap_key: String = r.get(0)?;
+            let currency: String = r.get(4)?;
+            let mut pricing = Pricing::at(r.get(1)?, r.get(2)?, r.get(3)?, &currency);
+            pricing.product_id = (!iap_key.is_empty()).then_some(iap_key);
+            pricing.converted_amount = r.get(5)?;
+            pricing.rate = r.get(6)?;
+            pricing.base_currency = pricing.converted_amount.map(|_| base.clone());
+            pricing.fetched_at = r.get(7)?;
+            Ok(pricing)
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    /// Add the products and region count to a snapshot read from `snapshots`.
+    fn fill(&self, mut snapshot: Snapshot) -> Result<Snapshot> {
+        let mut stmt = self.conn.prepare(
+            "SELECT iap_key FROM prices WHERE snapshot_id = ?1 AND iap_key != ''
+             GROUP BY iap_key ORDER BY MIN(rowid)",
+        )?;
+        snapshot.iap_keys = stmt.query_map([snapshot.id], |r| r.get(0))?.collect::<rusqlite::Result<_>>()?;
+        snapshot.regions = self.conn.query_row(
+            "SELECT COUNT(DISTINCT region_code) FROM prices WHERE snapshot_id = ?1",
+            [snapshot.id],
+            |r| r.get(0),
+        )?;
+        Ok(snapshot)
+    }
+}
+
+fn snapshot_head(r: &Row) -> rusqlite::Result<Snapshot> {
+    Ok(Snapshot {
+        id: r.get(0)?,
+        app_id: r.get(1)?,
+        app_name: r.get(2)?,
+        base_currency: r.get(3)?,
+        taken_at: r.get(4)?,
+        iap_keys: Vec::new(),
+        regions: 0,
+    })
+}
@@ -0,0 +1,30 @@
+//! Look up App Store prices for an app or one of its in-app purchases across
+//! every storefront, and convert them to a common base currency.
+//!
+//! [`PricingClient`] is the main entry point; the `appstore_pricing` binary is
+//! a thin interactive front end over it.
+
+mod appstore;
+pub mod batch;
+pub mod cache;
+mod client;
+pub mod currency;
+pub mod diff;
+pub mod extract;
+pub mod fx;
+pub mod history;
+pub mod link;
+pub mod notify;
+pub mod output;
+mod pricing;
+pub mod ratelimit;
+pub mod regions;
+pub mod retry;
+mod shoebox;
+pub mod transport;
+
+pub use appstore::{DEFAULT_CATALOG_URL, DEFAULT_STORE_URL};
+pub use client::{PricingClient, DEFAULT_CONCURRENCY};
+pub use currency::format_price;
+pub use pricing::{convert_prices, period_in_months, InAppPurchase, Pricing, RegionError, RegionOutcome};
+pub use regions::{find_region, home_region, Region, RegionFilter, RegionGroup, REGIONS};
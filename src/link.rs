@@ -0,0 +1,86 @@
+//! Turning whatever the user pasted — an App ID, a store URL, an
+//! `itms-apps://` deep link or an `apple.co` short link — into an App ID.
+
+use std::fmt;
+use reqwest::Url;
+use crate::regions::{find_region, Region};
+
+/// An app reference read from a link, with whatever else the link says.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AppLink {
+    pub app_id: String,
+    /// The storefront in the URL path, e.g. `gb` in `apps.apple.com/gb/app/...`.
+    pub storefront: Option<Region>,
+    /// The `l=` language parameter, e.g. `fr` or `en-GB`.
+    pub language: Option<String>,
+}
+
+/// Result of parsing user input without touching the network.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParsedLink {
+    App(AppLink),
+    /// An `apple.co` short link; it has to be fetched to learn the app.
+    Short(String),
+}
+
+/// Why input isn't an app reference.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinkError(String);
+
+impl fmt::Display for LinkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for LinkError {}
+
+const SCHEMES: &[&str] = &["https", "http", "itms-apps", "itms-appss", "macappstore"];
+const STORE_HOSTS: &[&str] = &["apps.apple.com", "itunes.apple.com", "geo.itunes.apple.com"];
+const SHORT_HOSTS: &[&str] = &["apple.co"];
+const NOT_APPS: &[&str] = &["developer", "app-bundle", "story", "room"];
+
+/// `123456789` or `id123456789`.
+fn bare_id(s: &str) -> Option<&str> {
+    let digits = s.strip_prefix("id").unwrap_or(s);
+    (!digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit())).then_some(digits)
+}
+
+/// Parse an App ID, store URL or deep link. Only the final `id<digits>` path
+/// segment (or an `id=` query parameter) is taken as the ID, so slugs that
+/// happen to contain "id" don't confuse it.
+pub fn parse_app_link(input: &str) -> Result<ParsedLink, LinkError> {
+    let input = input.trim();
+    if let Some(id) = bare_id(input) {
+        return Ok(ParsedLink::App(AppLink { app_id: id.to_string(), storefront: None, language: None }));
+    }
+    let with_scheme = if input.contains("://") { input.to_string() } else { format!("https://{}", input) };
+    let url = Url::parse(&with_scheme).map_err(|e| LinkError(format!("Not an App Store link: {}", e)))?;
+    if !SCHEMES.contains(&url.scheme()) {
+        return Err(LinkError(format!("Unsupported link scheme `{}`", url.scheme())));
+    }
+    let host = url.host_str().unwrap_or_default().to_ascii_lowercase();
+    if SHORT_HOSTS.contains(&host.as_str()) {
+        return Ok(ParsedLink::Short(url.to_string()));
+    }
+    if !STORE_HOSTS.contains(&host.as_str()) {
+        return Err(LinkError(format!("`{}` is not an App Store host", host)));
+    }
+
+    let segments: Vec<&str> = url.path_segments().map(|s| s.filter(|s| !s.is_empty()).collect()).unwrap_or_default();
+    // Developer pages and bundles carry an `id` too, but not an app's.
+    if let Some(kind) = segments.iter().find(|s| NOT_APPS.contains(s)) {
+        return Err(LinkError(format!("This is an App Store `{}` link, not an app", kind)));
+    }
+    let query = |key: &str| url.query_pairs().find(|(k, _)| k == key).map(|(_, v)| v.into_owned());
+    let app_id = segments
+        .iter()
+        .rev()
+        .find_map(|s| s.strip_prefix("id").and_then(bare_id))
+        .map(str::to_string)
+        .or_else(|| query("id").filter(|id| bare_id(id).is_some()))
+        .ok_or_else(|| LinkError("No App ID (`id` followed by digits) in the link".into()))?;
+    let storefront = segments.first().filter(|s| s.len() == 2).and_then(|s| find_region(s));
+    let language = query("l").filter(|l| !l.is_empty());
+    Ok(ParsedLink::App(AppLink { app_id, storefront, language }))
+}
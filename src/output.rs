@@ -0,0 +1,518 @@
+//! Rendering collected prices as a terminal table or machine-readable text.
+
+use std::fmt;
+use std::str::FromStr;
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use crate::batch::AppPrices;
+use crate::currency::format_price;
+use crate::diff::{ChangeKind, PriceChange};
+use crate::history::Snapshot;
+use crate::pricing::{InAppPurchase, Pricing};
+
+/// Column names shared by the CSV, TSV and Markdown outputs. They match the
+/// field names of [`Pricing`], which is what the JSON output uses.
+pub const COLUMNS: [&str; 18] = [
+    "region_code",
+    "region_name",
+    "amount",
+    "currency",
+    "converted_amount",
+    "base_currency",
+    "rate",
+    "fetched_at",
+    "product_id",
+    "match_note",
+    "catalog_complete",
+    "offer_type",
+    "billing_period",
+    "intro_offer",
+    "intro_price",
+    "intro_period",
+    "intro_periods",
+    "converted_monthly",
+];
+
+/// Column names of the CSV, TSV and Markdown change reports, matching the
+/// field names of [`PriceChange`].
+pub const CHANGE_COLUMNS: [&str; 12] = [
+    "region_code",
+    "region_name",
+    "product_id",
+    "change",
+    "old_amount",
+    "old_currency",
+    "new_amount",
+    "new_currency",
+    "amount_change_pct",
+    "old_converted",
+    "new_converted",
+    "converted_change_pct",
+];
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Boxed table with formatted prices, for people.
+    Table,
+    Json,
+    Csv,
+    Tsv,
+    Markdown,
+}
+
+impl FromStr for OutputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "table" => Ok(OutputFormat::Table),
+            "json" => Ok(OutputFormat::Json),
+            "csv" => Ok(OutputFormat::Csv),
+            "tsv" => Ok(OutputFormat::Tsv),
+            "markdown" | "md" => Ok(OutputFormat::Markdown),
+            other => Err(anyhow!("unknown output format `{}` (expected table, json, csv, tsv or markdown)", other)),
+        }
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            OutputFormat::Table => "table",
+            OutputFormat::Json => "json",
+            OutputFormat::Csv => "csv",
+            OutputFormat::Tsv => "tsv",
+            OutputFormat::Markdown => "markdown",
+        })
+    }
+}
+
+/// Render `pricing` in the requested format. The result ends with a newline.
+pub fn render(pricing: &[Pricing], format: OutputFormat, base_currency: &str) -> Result<String> {
+    Ok(match format {
+        OutputFormat::Table => render_table(pricing, base_currency),
+        OutputFormat::Json => serde_json::to_string_pretty(pricing)? + "\n",
+        OutputFormat::Csv | OutputFormat::Tsv | OutputFormat::Markdown => {
+            let rows: Vec<Vec<String>> = pricing.iter().map(|e| record(e).to_vec()).collect();
+            render_records(&COLUMNS, &rows, format)
+        }
+    })
+}
+
+/// Render prices of several in-app purchases. Table and Markdown show a
+/// matrix with a row per region and a column per IAP, in `iaps` order; the
+/// machine-readable formats keep one record per region and IAP, as
+/// [`render`] does.
+pub fn render_matrix(
+    pricing: &[Pricing],
+    iaps: &[InAppPurchase],
+    format: OutputFormat,
+    base_currency: &str,
+) -> Result<String> {
+    let (headers, rows) = match format {
+        OutputFormat::Table | OutputFormat::Markdown => matrix(pricing, iaps, base_currency),
+        _ => return render(pricing, format, base_currency),
+    };
+    Ok(if format == OutputFormat::Table {
+        draw_table(&headers, &rows)
+    } else {
+        render_markdown(&headers.iter().map(String::as_str).collect::<Vec<_>>(), &rows)
+    })
+}
+
+/// Render several apps' prices as one report. Table and Markdown show a
+/// matrix with a row per region and a column per app, or per app and
+/// in-app purchase; the other formats give one record per app, region and
+/// product, with `app_id` and `app_name` ahead of [`COLUMNS`].
+pub fn render_batch(apps: &[AppPrices], format: OutputFormat, base_currency: &str) -> Result<String> {
+    #[derive(Serialize)]
+    struct Record<'a> {
+        app_id: &'a str,
+        app_name: Option<&'a str>,
+        #[serde(flatten)]
+        pricing: &'a Pricing,
+    }
+    let records = apps.iter().flat_map(|a| {
+        a.pricing.iter().map(move |p| Record { app_id: &a.app_id, app_name: a.app_name.as_deref(), pricing: p })
+    });
+    match format {
+        OutputFormat::Json => return Ok(serde_json::to_string_pretty(&records.collect::<Vec<_>>())? + "\n"),
+        OutputFormat::Csv | OutputFormat::Tsv => {
+            let headers: Vec<&str> = ["app_id", "app_name"].into_iter().chain(COLUMNS).collect();
+            let rows: Vec<Vec<String>> = records
+                .map(|r| {
+                    let mut row = vec![r.app_id.to_string(), r.app_name.unwrap_or_default().to_string()];
+                    row.extend(record(r.pricing));
+                    row
+                })
+                .collect();
+            return Ok(render_records(&headers, &rows, format));
+        }
+        OutputFormat::Table | OutputFormat::Markdown => {}
+    }
+
+    // One column per app and product, in the order they were priced.
+    let mut columns: Vec<(&str, Option<&str>, String)> = Vec::new();
+    for app in apps {
+        let name = app.app_name.as_deref().unwrap_or(&app.app_id);
+        for e in &app.pricing {
+            let product = e.product_id.as_deref();
+            if !columns.iter().any(|(id, p, _)| *id == app.app_id && *p == product) {
+                let header = product.map_or(name.to_string(), |p| format!("{}: {}", name, p));
+                columns.push((&app.app_id, product, header));
+            }
+        }
+    }
+    let mut regions: Vec<(&str, &str)> = Vec::new();
+    for e in apps.iter().flat_map(|a| &a.pricing) {
+        if !regions.iter().any(|(code, _)| *code == e.region_code) {
+            regions.push((&e.region_code, &e.region_name));
+        }
+    }
+    regions.sort_by_key(|(_, name)| *name);
+
+    let mut headers = vec!["Region".to_string()];
+    headers.extend(columns.iter().map(|(_, _, header)| header.clone()));
+    let rows: Vec<Vec<String>> = regions
+        .iter()
+        .map(|(code, name)| {
+            let mut row = vec![name.to_string()];
+            row.extend(columns.iter().map(|(app_id, product, _)| {
+                let entry = apps
+                    .iter()
+                    .filter(|a| a.app_id == *app_id)
+                    .flat_map(|a| &a.pricing)
+                    .find(|e| e.region_code == *code && e.product_id.as_deref() == *product);
+                entry.map_or("—".to_string(), |e| matrix_cell(e, base_currency))
+            }));
+            row
+        })
+        .collect();
+    Ok(if format == OutputFormat::Table {
+        draw_table(&headers, &rows)
+    } else {
+        render_markdown(&headers.iter().map(String::as_str).collect::<Vec<_>>(), &rows)
+    })
+}
+
+/// Render a list of history snapshots. Table and Markdown list each
+/// snapshot's products; the delimited formats give them `;`-separated.
+pub fn render_snapshots(snapshots: &[Snapshot], format: OutputFormat) -> Result<String> {
+    let headers = ["id", "taken_at", "app_id", "app_name", "base_currency", "regions", "iap_keys"];
+    let rows: Vec<Vec<String>> = snapshots
+        .iter()
+        .map(|s| {
+            vec![
+                s.id.to_string(),
+                s.taken_at.to_rfc3339(),
+                s.app_id.clone(),
+                s.app_name.clone().unwrap_or_default(),
+                s.base_currency.clone(),
+                s.regions.to_string(),
+                s.iap_keys.join(";"),
+            ]
+        })
+        .collect();
+    Ok(match format {
+        OutputFormat::Json => serde_json::to_string_pretty(snapshots)? + "\n",
+        OutputFormat::Table => {
+            let headers = ["#", "Taken", "App ID", "App", "Base", "Regions", "In-app purchases"].map(String::from);
+            let rows: Vec<Vec<String>> = snapshots
+                .iter()
+                .zip(rows)
+                .map(|(s, mut row)| {
+                    row[1] = s.taken_at.format("%Y-%m-%d %H:%M").to_string();
+                    row[6] = if s.iap_keys.is_empty() { "(app)".into() } else { s.iap_keys.join(", ") };
+                    row
+                })
+                .collect();
+            draw_table(&headers, &rows)
+        }
+        _ => render_records(&headers, &rows, format),
+    })
+}
+
+/// Comma- or tab-separated values, or a Markdown table, of raw `rows`.
+fn render_records(headers: &[&str], rows: &[Vec<String>], format: OutputFormat) -> String {
+    match format {
+        OutputFormat::Csv => render_delimited(headers, rows, ',', csv_field),
+        OutputFormat::Tsv => render_delimited(headers, rows, '\t', tsv_field),
+        _ => render_markdown(headers, rows),
+    }
+}
+
+/// Render price changes. The table shows formatted prices and signed
+/// percentages; the other formats give one record per change with the
+/// fields of [`PriceChange`].
+pub fn render_changes(changes: &[PriceChange], format: OutputFormat, base_currency: &str) -> Result<String> {
+    let num = |v: Option<f64>| v.map(|v| v.to_string()).unwrap_or_default();
+    let text = |v: &Option<String>| v.clone().unwrap_or_default();
+    match format {
+        OutputFormat::Json => return Ok(serde_json::to_string_pretty(changes)? + "\n"),
+        OutputFormat::Table => {}
+        _ => {
+            let rows: Vec<Vec<String>> = changes
+                .iter()
+                .map(|c| {
+                    vec![
+                        c.region_code.clone(),
+                        c.region_name.clone(),
+                        text(&c.product_id),
+                        change_label(c.change).to_string(),
+                        num(c.old_amount),
+                        text(&c.old_currency),
+                        num(c.new_amount),
+                        text(&c.new_currency),
+                        num(c.amount_change_pct),
+                        num(c.old_converted),
+                        num(c.new_converted),
+                        num(c.converted_change_pct),
+                    ]
+                })
+                .collect();
+            return Ok(render_records(&CHANGE_COLUMNS, &rows, format));
+        }
+    }
+
+    let products = changes.iter().any(|c| c.product_id.is_some());
+    let mut headers = vec!["Region".to_string()];
+    if products {
+        headers.push("In-app purchase".to_string());
+    }
+    headers.extend([
+        "Change".to_string(),
+        "Before".to_string(),
+        "After".to_string(),
+        "Δ".to_string(),
+        format!("Before ({})", base_currency),
+        format!("After ({})", base_currency),
+        format!("Δ ({})", base_currency),
+    ]);
+    let price = |amount: Option<f64>, currency: &Option<String>| match (amount, currency) {
+        (Some(a), Some(c)) => format_price(a, c),
+        _ => String::new(),
+    };
+    let base = |v: Option<f64>| v.map_or(String::new(), |v| format_price(v, base_currency));
+    let pct = |v: Option<f64>| v.map_or(String::new(), |v| format!("{:+.1}%", v));
+    let rows: Vec<Vec<String>> = changes
+        .iter()
+        .map(|c| {
+            let mut row = vec![c.region_name.clone()];
+            if products {
+                row.push(text(&c.product_id));
+            }
+            row.extend([
+                change_label(c.change).to_string(),
+                price(c.old_amount, &c.old_currency),
+                price(c.new_amount, &c.new_currency),
+                pct(c.amount_change_pct),
+                base(c.old_converted),
+                base(c.new_converted),
+                pct(c.converted_change_pct),
+            ]);
+            row
+        })
+        .collect();
+    Ok(draw_table(&headers, &rows))
+}
+
+fn change_label(kind: ChangeKind) -> &'static str {
+    match kind {
+        ChangeKind::Added => "added",
+        ChangeKind::Removed => "removed",
+        ChangeKind::Changed => "changed",
+    }
+}
+
+/// Header and rows of the region × IAP matrix. Products priced but missing
+/// from `iaps` get a column of their own, headed by their product ID.
+fn matrix(pricing: &[Pricing], iaps: &[InAppPurchase], base_currency: &str) -> (Vec<String>, Vec<Vec<String>>) {
+    let mut products: Vec<(String, String)> =
+        iaps.iter().map(|p| (p.offer_name.clone(), p.name.clone())).collect();
+    for e in pricing {
+        if let Some(id) = &e.product_id {
+            if !products.iter().any(|(p, _)| p == id) {
+                products.push((id.clone(), id.clone()));
+            }
+        }
+    }
+    let mut regions: Vec<(&str, &str)> = Vec::new();
+    for e in pricing {
+        if !regions.iter().any(|(code, _)| *code == e.region_code) {
+            regions.push((&e.region_code, &e.region_name));
+        }
+    }
+    regions.sort_by_key(|(_, name)| *name);
+
+    let mut headers = vec!["Region".to_string()];
+    headers.extend(products.iter().map(|(_, name)| name.clone()));
+    let rows = regions
+        .iter()
+        .map(|(code, name)| {
+            let mut row = vec![name.to_string()];
+            row.extend(products.iter().map(|(id, _)| {
+                let entry = pricing
+                    .iter()
+                    .find(|e| e.region_code == *code && e.product_id.as_ref() == Some(id));
+                entry.map_or("—".to_string(), |e| matrix_cell(e, base_currency))
+            }));
+            row
+        })
+        .collect();
+    (headers, rows)
+}
+
+/// e.g. `¥500 ($3.33)`, or just `¥500` when it wasn't converted.
+fn matrix_cell(e: &Pricing, base_currency: &str) -> String {
+    match e.converted_amount {
+        Some(v) => format!("{} ({})", format_price(e.amount, &e.currency), format_price(v, base_currency)),
+        None => format_price(e.amount, &e.currency),
+    }
+}
+
+/// One entry's values, in `COLUMNS` order, without any quoting.
+fn record(e: &Pricing) -> [String; 18] {
+    let opt = |v: Option<f64>| v.map(|v| v.to_string()).unwrap_or_default();
+    [
+        e.region_code.clone(),
+        e.region_name.clone(),
+        e.amount.to_string(),
+        e.currency.clone(),
+        opt(e.converted_amount),
+        e.base_currency.clone().unwrap_or_default(),
+        opt(e.rate),
+        e.fetched_at.to_rfc3339(),
+        e.product_id.clone().unwrap_or_default(),
+        e.match_note.clone().unwrap_or_default(),
+        e.catalog_complete.map(|c| c.to_string()).unwrap_or_default(),
+        e.offer_type.clone().unwrap_or_default(),
+        e.billing_period.clone().unwrap_or_default(),
+        e.intro_offer.clone().unwrap_or_default(),
+        opt(e.intro_price),
+        e.intro_period.clone().unwrap_or_default(),
+        e.intro_periods.map(|n| n.to_string()).unwrap_or_default(),
+        opt(e.converted_monthly),
+    ]
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+fn tsv_field(s: &str) -> String {
+    s.replace(['\t', '\n', '\r'], " ")
+}
+
+fn markdown_field(s: &str) -> String {
+    s.replace('|', "\\|").replace(['\n', '\r'], " ")
+}
+
+fn render_delimited(headers: &[&str], rows: &[Vec<String>], sep: char, field: fn(&str) -> String) -> String {
+    let sep = sep.to_string();
+    let mut out = headers.iter().map(|h| field(h)).collect::<Vec<_>>().join(&sep) + "\n";
+    for row in rows {
+        out += &row.iter().map(|v| field(v)).collect::<Vec<_>>().join(&sep);
+        out.push('\n');
+    }
+    out
+}
+
+fn render_markdown(headers: &[&str], rows: &[Vec<String>]) -> String {
+    let cells = |values: Vec<String>| format!("| {} |\n", values.join(" | "));
+    let mut out = cells(headers.iter().map(|h| markdown_field(h)).collect());
+    out += &format!("|{}\n", "---|".repeat(headers.len()));
+    for row in rows {
+        out += &cells(row.iter().map(|v| markdown_field(v)).collect());
+    }
+    out
+}
+
+fn render_table(pricing: &[Pricing], base_currency: &str) -> String {
+    let mut headers = vec![
+        "Region".to_string(),
+        "Price".to_string(),
+        "Currency".to_string(),
+        format!("Converted ({})", base_currency),
+    ];
+    // Subscription columns only when there's a subscription to show.
+    let periodic = pricing.iter().any(|e| e.billing_period.is_some());
+    let intro = pricing.iter().any(|e| e.intro_offer.is_some());
+    if periodic {
+        headers.push("Period".to_string());
+        headers.push(format!("Per month ({})", base_currency));
+    }
+    if intro {
+        headers.push("Intro offer".to_string());
+    }
+    let rows: Vec<Vec<String>> = pricing.iter().map(|e| {
+        let mut row = vec![
+            e.region_name.clone(),
+            format_price(e.amount, &e.currency),
+            e.currency.clone(),
+            e.converted_amount
+                .map_or("N/A".into(), |v| format_price(v, base_currency))
+        ];
+        if periodic {
+            row.push(e.billing_period.clone().unwrap_or_default());
+            row.push(e.converted_monthly.map_or(String::new(), |v| format_price(v, base_currency)));
+        }
+        if intro {
+            row.push(intro_label(e));
+        }
+        row
+    }).collect();
+
+    draw_table(&headers, &rows)
+}
+
+/// e.g. `free-trial 1 × P1W` or `pay-as-you-go ¥120 3 × P1M`.
+fn intro_label(e: &Pricing) -> String {
+    let Some(mode) = &e.intro_offer else {
+        return String::new();
+    };
+    let mut label = mode.clone();
+    if let Some(price) = e.intro_price.filter(|p| *p > 0.0) {
+        label += &format!(" {}", format_price(price, &e.currency));
+    }
+    if let Some(period) = &e.intro_period {
+        label += &format!(" {} × {}", e.intro_periods.unwrap_or(1), period);
+    }
+    label
+}
+
+/// Boxed, centred table with a header row.
+fn draw_table(headers: &[String], rows: &[Vec<String>]) -> String {
+    let mut widths = headers.iter().map(|h| h.chars().count()).collect::<Vec<_>>();
+    for row in rows {
+        for (i, cell) in row.iter().enumerate() {
+            widths[i] = widths[i].max(cell.chars().count());
+        }
+    }
+    let border = |widths: &[usize]| {
+        let mut line = "+".to_string();
+        for w in widths {
+            line += &format!("{:-^1$}+", "-", w + 2);
+        }
+        line + "\n"
+    };
+    let mut out = border(&widths);
+    out.push('|');
+    for (i, h) in headers.iter().enumerate() {
+        out += &format!(" {:^width$} |", h, width = widths[i]);
+    }
+    out.push('\n');
+    out += &border(&widths);
+    for row in rows {
+        out.push('|');
+        for (i, cell) in row.iter().enumerate() {
+            out += &format!(" {:^width$} |", cell, width = widths[i]);
+        }
+        out.push('\n');
+    }
+    out += &border(&widths);
+    out
+}
@@ -0,0 +1,246 @@
+use std::collections::HashMap;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use crate::regions::Region;
+use crate::shoebox::InAppResource;
+
+/// Average length of a month in days, for normalising weekly and daily periods.
+const DAYS_PER_MONTH: f64 = 365.25 / 12.0;
+
+/// Price of an app or in-app purchase in one storefront.
+///
+/// Field names double as the column names of every machine-readable output
+/// format, so treat renames as breaking changes.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Pricing {
+    pub region_code: String,
+    pub region_name: String,
+    pub amount: f64,
+    pub currency: String,
+    pub converted_amount: Option<f64>,
+    pub base_currency: Option<String>,
+    /// Units of `currency` per one unit of `base_currency`.
+    pub rate: Option<f64>,
+    pub fetched_at: DateTime<Utc>,
+    /// Product identifier of the in-app purchase priced; `None` for the app itself.
+    pub product_id: Option<String>,
+    /// How the in-app purchase was identified when this storefront lists it
+    /// under a different ID and product identifier, e.g.
+    /// `matched by name similarity to "Pro-Monthly"`.
+    pub match_note: Option<String>,
+    /// For in-app purchases, whether the storefront's IAP list this price
+    /// came from was complete or only Apple's `top-in-apps` shortlist.
+    pub catalog_complete: Option<bool>,
+    /// Kind of in-app purchase: `consumable`, `non-consumable`,
+    /// `auto-renewable-subscription` or `non-renewing-subscription`.
+    pub offer_type: Option<String>,
+    /// ISO 8601 billing period of a subscription, e.g. `P1M` or `P1Y`.
+    pub billing_period: Option<String>,
+    /// Introductory offer mode: `free-trial`, `pay-as-you-go` or `pay-up-front`.
+    pub intro_offer: Option<String>,
+    /// Price of the introductory offer, per intro period, in `currency`.
+    pub intro_price: Option<f64>,
+    /// ISO 8601 length of one introductory period.
+    pub intro_period: Option<String>,
+    /// How many introductory periods the offer lasts.
+    pub intro_periods: Option<u32>,
+    /// `converted_amount` spread over `billing_period`, per month, so
+    /// subscriptions of different durations can be compared.
+    pub converted_monthly: Option<f64>,
+}
+
+impl Pricing {
+    /// A freshly fetched, not yet converted price.
+    pub fn new(region: &Region, amount: f64, currency: &str) -> Self {
+        Pricing::at(region.code.to_string(), region.name.to_string(), amount, currency)
+    }
+
+    /// A price in a storefront known only by code and name, e.g. one read
+    /// back from the history database.
+    pub(crate) fn at(region_code: String, region_name: String, amount: f64, currency: &str) -> Self {
+        Pricing {
+            region_code,
+            region_name,
+            amount,
+            currency: currency.to_string(),
+            converted_amount: None,
+            base_currency: None,
+            rate: None,
+            fetched_at: Utc::now(),
+            product_id: None,
+            match_note: None,
+            catalog_complete: None,
+            offer_type: None,
+            billing_period: None,
+            intro_offer: None,
+            intro_price: None,
+            intro_period: None,
+            intro_periods: None,
+            converted_monthly: None,
+        }
+    }
+
+    /// The current offer of an in-app purchase, with any subscription
+    /// period and introductory offer, keyed by its own product identifier.
+    pub(crate) fn for_offer(region: &Region, item: &InAppResource) -> Result<Self, RegionError> {
+        let offer = item.offer()?;
+        let mut pricing = Pricing::new(region, offer.price, &offer.currency_code).for_product(&item.attributes.offer_name);
+        pricing.offer_type = item.attributes.kind.as_deref().map(kebab_case);
+        pricing.billing_period = offer.recurring_subscription_period.clone();
+        if let Some(intro) = offer.discounts.first() {
+            pricing.intro_offer = Some(kebab_case(&intro.mode_type));
+            pricing.intro_price = intro.price;
+            pricing.intro_period = intro.recurring_subscription_period.clone();
+            pricing.intro_periods = intro.num_of_periods;
+        }
+        Ok(pricing)
+    }
+
+    /// Tag the price with the in-app purchase it belongs to.
+    pub fn for_product(mut self, product_id: &str) -> Self {
+        self.product_id = Some(product_id.to_string());
+        self
+    }
+}
+
+/// An in-app purchase listed under an app's `top-in-apps` relationship.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct InAppPurchase {
+    /// Apple's numeric resource ID.
+    pub id: String,
+    /// Display name as shown on the storefront.
+    pub name: String,
+    /// Developer-assigned product identifier.
+    pub offer_name: String,
+    /// Storefront-formatted price, e.g. `$4.99`.
+    pub price_formatted: String,
+    /// 0-based rank by price among the IAPs listed alongside it (cheapest
+    /// first), used to recognise it in storefronts that rename it.
+    pub price_tier: Option<usize>,
+}
+
+impl From<&InAppResource> for InAppPurchase {
+    fn from(item: &InAppResource) -> Self {
+        InAppPurchase {
+            id: item.id.clone(),
+            name: item.attributes.name.clone(),
+            offer_name: item.attributes.offer_name.clone(),
+            price_formatted: item
+                .attributes
+                .offers
+                .first()
+                .and_then(|o| o.price_formatted.clone())
+                .unwrap_or_default(),
+            price_tier: None,
+        }
+    }
+}
+
+/// `Auto-Renewable Subscription` → `auto-renewable-subscription`,
+/// `FreeTrial` → `free-trial`.
+fn kebab_case(s: &str) -> String {
+    let mut out = String::new();
+    for (i, c) in s.chars().enumerate() {
+        if c == ' ' || c == '_' || c == '-' {
+            if !out.ends_with('-') {
+                out.push('-');
+            }
+        } else if c.is_uppercase() {
+            if i > 0 && !out.ends_with('-') {
+                out.push('-');
+            }
+            out.extend(c.to_lowercase());
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Length of an ISO 8601 period such as `P1M`, `P1Y`, `P1W` or `P3D` in
+/// months. Weeks and days count against an average month.
+pub fn period_in_months(period: &str) -> Option<f64> {
+    let mut rest = period.strip_prefix('P')?;
+    let mut days = 0.0;
+    let mut months = 0.0;
+    while !rest.is_empty() {
+        let digits = rest.find(|c: char| !c.is_ascii_digit())?;
+        let n: f64 = rest[..digits].parse().ok()?;
+        match rest[digits..].chars().next()? {
+            'Y' => months += 12.0 * n,
+            'M' => months += n,
+            'W' => days += 7.0 * n,
+            'D' => days += n,
+            _ => return None,
+        }
+        rest = &rest[digits + 1..];
+    }
+    let total = months + days / DAYS_PER_MONTH;
+    (total > 0.0).then_some(total)
+}
+
+/// Fill in `converted_amount` from a table of rates relative to `base_currency`,
+/// then sort cheapest first. Entries without a usable (finite, positive)
+/// rate sort to the front. Subscriptions also get a per-month
+/// `converted_monthly`.
+pub fn convert_prices(pricing: &mut [Pricing], base_currency: &str, rates: &HashMap<String, f64>) {
+    for entry in pricing.iter_mut() {
+        if let Some(rate) = rates.get(&entry.currency).filter(|r| r.is_finite() && **r > 0.0) {
+            let converted = entry.amount / rate;
+            entry.converted_amount = Some((converted * 100.0).round() / 100.0);
+            entry.base_currency = Some(base_currency.to_string());
+            entry.rate = Some(*rate);
+            entry.converted_monthly = entry
+                .billing_period
+                .as_deref()
+                .and_then(period_in_months)
+                .map(|months| (converted / months * 100.0).round() / 100.0);
+        }
+    }
+    // Unconverted entries first, as `Option`'s ordering has it.
+    pricing.sort_by(|a, b| match (a.converted_amount, b.converted_amount) {
+        (Some(a), Some(b)) => a.total_cmp(&b),
+        (a, b) => a.is_some().cmp(&b.is_some()),
+    });
+}
+
+/// What a single storefront had to say about the requested app or IAP.
+// `Found` is the common case, so boxing it would only add an allocation.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone)]
+pub enum RegionOutcome {
+    /// A price with an amount and currency.
+    Found(Pricing),
+    /// Only a display label was found (legacy page layout), e.g. `"4,99 €"`.
+    Label(String),
+    /// The page loaded but lists no price for the requested item.
+    NoPrice,
+    /// The app isn't sold in this storefront.
+    NotAvailable,
+}
+
+/// Why a storefront couldn't be priced, after any retries.
+#[derive(Debug, Clone)]
+pub enum RegionError {
+    /// Apple kept answering 429 Too Many Requests.
+    RateLimited,
+    /// The page didn't arrive within the request timeout.
+    TimedOut,
+    /// The page couldn't be fetched (network error or unexpected HTTP status).
+    Fetch(String),
+    /// The page was fetched but its price data couldn't be read.
+    Parse(String),
+}
+
+impl std::fmt::Display for RegionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RegionError::RateLimited => write!(f, "rate limited by the App Store"),
+            RegionError::TimedOut => write!(f, "timed out fetching page"),
+            RegionError::Fetch(msg) => write!(f, "error fetching page: {}", msg),
+            RegionError::Parse(msg) => write!(f, "error reading price data: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for RegionError {}
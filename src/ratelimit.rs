@@ -0,0 +1,70 @@
+//! Per-host token-bucket rate limiting for outgoing requests.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Allows `per_second` requests per host on average, with bursts of up to
+/// `burst`. Callers over the limit wait their turn rather than failing.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    pub per_second: f64,
+    pub burst: u32,
+}
+
+impl Default for RateLimit {
+    fn default() -> Self {
+        RateLimit { per_second: 10.0, burst: 10 }
+    }
+}
+
+/// Shared token buckets, one per host.
+pub struct RateLimiter {
+    limit: Option<RateLimit>,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl RateLimiter {
+    /// `None` disables limiting.
+    pub fn new(limit: Option<RateLimit>) -> Self {
+        RateLimiter { limit, buckets: Mutex::new(HashMap::new()) }
+    }
+
+    /// Wait until a request to `host` is allowed.
+    pub async fn acquire(&self, host: &str) {
+        let Some(limit) = self.limit.filter(|l| l.per_second > 0.0) else {
+            return;
+        };
+        let burst = f64::from(limit.burst.max(1));
+        let wait = {
+            let mut buckets = self.buckets.lock().unwrap();
+            let now = Instant::now();
+            let bucket = buckets
+                .entry(host.to_string())
+                .or_insert(Bucket { tokens: burst, updated: now });
+            let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+            bucket.tokens = (bucket.tokens + elapsed * limit.per_second).min(burst);
+            bucket.updated = now;
+            // Take the token now, even if that leaves the bucket in debt;
+            // the debt is how long this caller has to wait.
+            bucket.tokens -= 1.0;
+            (-bucket.tokens).max(0.0) / limit.per_second
+        };
+        if wait > 0.0 {
+            tokio::time::sleep(Duration::from_secs_f64(wait)).await;
+        }
+    }
+}
+
+/// The host a request to `url` is rate limited under.
+pub(crate) fn host_of(url: &str) -> String {
+    reqwest::Url::parse(url)
+        .ok()
+        .and_then(|u| u.host_str().map(str::to_string))
+        .unwrap_or_default()
+}
@@ -0,0 +1,366 @@
+use std::fmt;
+use std::str::FromStr;
+use anyhow::{anyhow, bail, Result};
+
+/// Broad region a storefront belongs to.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum RegionGroup {
+    /// Africa, Middle East, and India.
+    MiddleEastAfrica,
+    AsiaPacific,
+    Europe,
+    /// Latin America and the Caribbean.
+    LatinAmerica,
+    NorthAmerica,
+    Oceania,
+}
+
+impl RegionGroup {
+    pub const ALL: [RegionGroup; 6] = [
+        RegionGroup::MiddleEastAfrica,
+        RegionGroup::AsiaPacific,
+        RegionGroup::Europe,
+        RegionGroup::LatinAmerica,
+        RegionGroup::NorthAmerica,
+        RegionGroup::Oceania,
+    ];
+
+    /// Heading used in prompts and reports.
+    pub fn label(self) -> &'static str {
+        match self {
+            RegionGroup::MiddleEastAfrica => "Africa, Middle East, and India",
+            RegionGroup::AsiaPacific => "Asia Pacific",
+            RegionGroup::Europe => "Europe",
+            RegionGroup::LatinAmerica => "Latin America and the Caribbean",
+            RegionGroup::NorthAmerica => "North America",
+            RegionGroup::Oceania => "Oceania",
+        }
+    }
+}
+
+impl FromStr for RegionGroup {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "mea" => Ok(RegionGroup::MiddleEastAfrica),
+            "apac" => Ok(RegionGroup::AsiaPacific),
+            "europe" | "eu" => Ok(RegionGroup::Europe),
+            "latam" => Ok(RegionGroup::LatinAmerica),
+            "na" => Ok(RegionGroup::NorthAmerica),
+            "oceania" => Ok(RegionGroup::Oceania),
+            other => Err(anyhow!("unknown region group `{}` (expected europe, apac, latam, mea, na or oceania)", other)),
+        }
+    }
+}
+
+impl fmt::Display for RegionGroup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            RegionGroup::MiddleEastAfrica => "mea",
+            RegionGroup::AsiaPacific => "apac",
+            RegionGroup::Europe => "europe",
+            RegionGroup::LatinAmerica => "latam",
+            RegionGroup::NorthAmerica => "na",
+            RegionGroup::Oceania => "oceania",
+        })
+    }
+}
+
+/// An App Store storefront and what it defaults to.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Region {
+    /// ISO 3166-1 alpha-2 code (`XK` for Kosovo), as used in store URLs.
+    pub code: &'static str,
+    pub name: &'static str,
+    /// Apple's numeric storefront ID, as sent in `X-Apple-Store-Front`.
+    pub storefront_id: u32,
+    /// ISO 4217 currency prices are normally listed in.
+    pub currency: &'static str,
+    /// ISO 639-1 code of the storefront's default language.
+    pub language: &'static str,
+    pub group: RegionGroup,
+    /// Whether listed prices include VAT/GST. US and Canadian prices are
+    /// shown before sales tax.
+    pub tax_inclusive: bool,
+}
+
+impl Region {
+    const fn new(
+        code: &'static str,
+        name: &'static str,
+        storefront_id: u32,
+        currency: &'static str,
+        language: &'static str,
+        group: RegionGroup,
+    ) -> Self {
+        Region { code, name, storefront_id, currency, language, group, tax_inclusive: true }
+    }
+
+    const fn excluding_tax(self) -> Self {
+        Region { tax_inclusive: false, ..self }
+    }
+}
+
+const MEA: RegionGroup = RegionGroup::MiddleEastAfrica;
+const APAC: RegionGroup = RegionGroup::AsiaPacific;
+const EUROPE: RegionGroup = RegionGroup::Europe;
+const LATAM: RegionGroup = RegionGroup::LatinAmerica;
+const NORTH_AMERICA: RegionGroup = RegionGroup::NorthAmerica;
+const OCEANIA: RegionGroup = RegionGroup::Oceania;
+
+/// Every storefront the tool knows about, grouped the way App Store Connect
+/// lists them. Each storefront appears once.
+pub const REGIONS: &[Region] = &[
+    // Africa, Middle East, and India
+    Region::new("DZ", "Algeria", 143563, "USD", "fr", MEA),
+    Region::new("AO", "Angola", 143564, "USD", "pt", MEA),
+    Region::new("BJ", "Benin", 143576, "USD", "fr", MEA),
+    Region::new("BW", "Botswana", 143525, "USD", "en", MEA),
+    Region::new("BF", "Burkina Faso", 143578, "USD", "fr", MEA),
+    Region::new("CM", "Cameroon", 143574, "USD", "fr", MEA),
+    Region::new("CI", "Côte d’Ivoire", 143527, "USD", "fr", MEA),
+    Region::new("CD", "Democratic Republic of the Congo", 143613, "USD", "fr", MEA),
+    Region::new("EG", "Egypt", 143516, "EGP", "ar", MEA),
+    Region::new("GH", "Ghana", 143573, "USD", "en", MEA),
+    Region::new("GW", "Guinea-Bissau", 143585, "USD", "pt", MEA),
+    Region::new("IN", "India", 143467, "INR", "en", MEA),
+    Region::new("IL", "Israel", 143491, "ILS", "he", MEA),
+    Region::new("JO", "Jordan", 143528, "USD", "ar", MEA),
+    Region::new("KE", "Kenya", 143529, "USD", "en", MEA),
+    Region::new("KW", "Kuwait", 143493, "USD", "ar", MEA),
+    Region::new("LR", "Liberia", 143588, "USD", "en", MEA),
+    Region::new("LY", "Libya", 143567, "USD", "ar", MEA),
+    Region::new("MG", "Madagascar", 143531, "USD", "fr", MEA),
+    Region::new("MW", "Malawi", 143589, "USD", "en", MEA),
+    Region::new("ML", "Mali", 143532, "USD", "fr", MEA),
+    Region::new("MR", "Mauritania", 143590, "USD", "ar", MEA),
+    Region::new("MU", "Mauritius", 143533, "USD", "en", MEA),
+    Region::new("MA", "Morocco", 143620, "USD", "fr", MEA),
+    Region::new("MZ", "Mozambique", 143593, "USD", "pt", MEA),
+    Region::new("NA", "Namibia", 143594, "USD", "en", MEA),
+    Region::new("NE", "Niger", 143534, "USD", "fr", MEA),
+    Region::new("NG", "Nigeria", 143561, "NGN", "en", MEA),
+    Region::new("OM", "Oman", 143562, "USD", "ar", MEA),
+    Region::new("PK", "Pakistan", 143477, "PKR", "en", MEA),
+    Region::new("QA", "Qatar", 143498, "QAR", "ar", MEA),
+    Region::new("RW", "Rwanda", 143621, "USD", "en", MEA),
+    Region::new("SA", "Saudi Arabia", 143479, "SAR", "ar", MEA),
+    Region::new("SN", "Senegal", 143535, "USD", "fr", MEA),
+    Region::new("SC", "Seychelles", 143599, "USD", "en", MEA),
+    Region::new("SL", "Sierra Leone", 143600, "USD", "en", MEA),
+    Region::new("ZA", "South Africa", 143472, "ZAR", "en", MEA),
+    Region::new("TZ", "Tanzania", 143572, "TZS", "en", MEA),
+    Region::new("TN", "Tunisia", 143536, "USD", "fr", MEA),
+    Region::new("UG", "Uganda", 143537, "USD", "en", MEA),
+    Region::new("AE", "United Arab Emirates", 143481, "AED", "ar", MEA),
+    Region::new("ZM", "Zambia", 143622, "USD", "en", MEA),
+    Region::new("ZW", "Zimbabwe", 143605, "USD", "en", MEA),
+
+    // Asia Pacific
+    Region::new("AU", "Australia", 143460, "AUD", "en", APAC),
+    Region::new("BD", "Bangladesh", 143490, "USD", "en", APAC),
+    Region::new("BT", "Bhutan", 143577, "USD", "en", APAC),
+    Region::new("BN", "Brunei Darussalam", 143560, "USD", "en", APAC),
+    Region::new("KH", "Cambodia", 143579, "USD", "en", APAC),
+    Region::new("CN", "China", 143465, "CNY", "zh", APAC),
+    Region::new("HK", "Hong Kong", 143463, "HKD", "zh", APAC),
+    Region::new("ID", "Indonesia", 143476, "IDR", "id", APAC),
+    Region::new("JP", "Japan", 143462, "JPY", "ja", APAC),
+    Region::new("KZ", "Kazakhstan", 143517, "KZT", "ru", APAC),
+    Region::new("KG", "Kyrgyzstan", 143586, "USD", "en", APAC),
+    Region::new("MO", "Macau", 143515, "USD", "zh", APAC),
+    Region::new("MY", "Malaysia", 143473, "MYR", "en", APAC),
+    Region::new("MV", "Maldives", 143488, "USD", "en", APAC),
+    Region::new("MN", "Mongolia", 143592, "USD", "en", APAC),
+    Region::new("MM", "Myanmar", 143570, "USD", "en", APAC),
+    Region::new("NP", "Nepal", 143484, "USD", "en", APAC),
+    Region::new("PH", "Philippines", 143474, "PHP", "en", APAC),
+    Region::new("SG", "Singapore", 143464, "SGD", "en", APAC),
+    Region::new("KR", "South Korea", 143466, "KRW", "ko", APAC),
+    Region::new("LK", "Sri Lanka", 143486, "USD", "en", APAC),
+    Region::new("TW", "Taiwan", 143470, "TWD", "zh", APAC),
+    Region::new("TJ", "Tajikistan", 143603, "USD", "en", APAC),
+    Region::new("TH", "Thailand", 143475, "THB", "th", APAC),
+    Region::new("TM", "Turkmenistan", 143604, "USD", "en", APAC),
+    Region::new("UZ", "Uzbekistan", 143566, "USD", "en", APAC),
+    Region::new("VN", "Vietnam", 143471, "VND", "vi", APAC),
+
+    // Europe
+    Region::new("AL", "Albania", 143575, "USD", "en", EUROPE),
+    Region::new("AM", "Armenia", 143524, "USD", "en", EUROPE),
+    Region::new("AT", "Austria", 143445, "EUR", "de", EUROPE),
+    Region::new("AZ", "Azerbaijan", 143568, "USD", "en", EUROPE),
+    Region::new("BY", "Belarus", 143565, "USD", "en", EUROPE),
+    Region::new("BE", "Belgium", 143446, "EUR", "nl", EUROPE),
+    Region::new("BA", "Bosnia and Herzegovina", 143612, "USD", "en", EUROPE),
+    Region::new("BG", "Bulgaria", 143526, "EUR", "bg", EUROPE),
+    Region::new("HR", "Croatia", 143494, "EUR", "hr", EUROPE),
+    Region::new("CY", "Cyprus", 143557, "EUR", "el", EUROPE),
+    Region::new("CZ", "Czech Republic", 143489, "CZK", "cs", EUROPE),
+    Region::new("DK", "Denmark", 143458, "DKK", "da", EUROPE),
+    Region::new("EE", "Estonia", 143518, "EUR", "et", EUROPE),
+    Region::new("FI", "Finland", 143447, "EUR", "fi", EUROPE),
+    Region::new("FR", "France", 143442, "EUR", "fr", EUROPE),
+    Region::new("GE", "Georgia", 143615, "USD", "en", EUROPE),
+    Region::new("DE", "Germany", 143443, "EUR", "de", EUROPE),
+    Region::new("GR", "Greece", 143448, "EUR", "el", EUROPE),
+    Region::new("HU", "Hungary", 143482, "HUF", "hu", EUROPE),
+    Region::new("IS", "Iceland", 143558, "USD", "en", EUROPE),
+    Region::new("IE", "Ireland", 143449, "EUR", "en", EUROPE),
+    Region::new("IT", "Italy", 143450, "EUR", "it", EUROPE),
+    Region::new("XK", "Kosovo", 143624, "USD", "en", EUROPE),
+    Region::new("LV", "Latvia", 143519, "EUR", "lv", EUROPE),
+    Region::new("LI", "Liechtenstein", 143522, "CHF", "de", EUROPE),
+    Region::new("LT", "Lithuania", 143520, "EUR", "lt", EUROPE),
+    Region::new("LU", "Luxembourg", 143451, "EUR", "fr", EUROPE),
+    Region::new("MT", "Malta", 143521, "EUR", "en", EUROPE),
+    Region::new("MD", "Moldova", 143523, "USD", "en", EUROPE),
+    Region::new("ME", "Montenegro", 143619, "USD", "en", EUROPE),
+    Region::new("NL", "Netherlands", 143452, "EUR", "nl", EUROPE),
+    Region::new("MK", "North Macedonia", 143530, "USD", "en", EUROPE),
+    Region::new("NO", "Norway", 143457, "NOK", "nb", EUROPE),
+    Region::new("PL", "Poland", 143478, "PLN", "pl", EUROPE),
+    Region::new("PT", "Portugal", 143453, "EUR", "pt", EUROPE),
+    Region::new("RO", "Romania", 143487, "RON", "ro", EUROPE),
+    Region::new("RU", "Russia", 143469, "RUB", "ru", EUROPE),
+    Region::new("SK", "Slovakia", 143496, "EUR", "sk", EUROPE),
+    Region::new("SI", "Slovenia", 143499, "EUR", "sl", EUROPE),
+    Region::new("ES", "Spain", 143454, "EUR", "es", EUROPE),
+    Region::new("SE", "Sweden", 143456, "SEK", "sv", EUROPE),
+    Region::new("CH", "Switzerland", 143459, "CHF", "de", EUROPE),
+    Region::new("TR", "Turkey", 143480, "TRY", "tr", EUROPE),
+    Region::new("UA", "Ukraine", 143492, "USD", "uk", EUROPE),
+    Region::new("GB", "United Kingdom", 143444, "GBP", "en", EUROPE),
+
+    // Latin America and the Caribbean
+    Region::new("AI", "Anguilla", 143538, "USD", "en", LATAM),
+    Region::new("AG", "Antigua and Barbuda", 143540, "USD", "en", LATAM),
+    Region::new("AR", "Argentina", 143505, "USD", "es", LATAM),
+    Region::new("BS", "Bahamas", 143539, "USD", "en", LATAM),
+    Region::new("BB", "Barbados", 143541, "USD", "en", LATAM),
+    Region::new("BZ", "Belize", 143555, "USD", "en", LATAM),
+    Region::new("BM", "Bermuda", 143542, "USD", "en", LATAM),
+    Region::new("BO", "Bolivia", 143556, "USD", "es", LATAM),
+    Region::new("BR", "Brazil", 143503, "BRL", "pt", LATAM),
+    Region::new("VG", "British Virgin Islands", 143543, "USD", "en", LATAM),
+    Region::new("KY", "Cayman Islands", 143544, "USD", "en", LATAM),
+    Region::new("CL", "Chile", 143483, "CLP", "es", LATAM),
+    Region::new("CO", "Colombia", 143501, "COP", "es", LATAM),
+    Region::new("CR", "Costa Rica", 143495, "USD", "es", LATAM),
+    Region::new("DM", "Dominica", 143545, "USD", "en", LATAM),
+    Region::new("DO", "Dominican Republic", 143508, "USD", "es", LATAM),
+    Region::new("EC", "Ecuador", 143509, "USD", "es", LATAM),
+    Region::new("SV", "El Salvador", 143506, "USD", "es", LATAM),
+    Region::new("GD", "Grenada", 143546, "USD", "en", LATAM),
+    Region::new("GT", "Guatemala", 143504, "USD", "es", LATAM),
+    Region::new("GY", "Guyana", 143553, "USD", "en", LATAM),
+    Region::new("HN", "Honduras", 143510, "USD", "es", LATAM),
+    Region::new("JM", "Jamaica", 143511, "USD", "en", LATAM),
+    Region::new("MX", "Mexico", 143468, "MXN", "es", LATAM),
+    Region::new("MS", "Montserrat", 143547, "USD", "en", LATAM),
+    Region::new("NI", "Nicaragua", 143512, "USD", "es", LATAM),
+    Region::new("PA", "Panama", 143485, "USD", "es", LATAM),
+    Region::new("PY", "Paraguay", 143513, "USD", "es", LATAM),
+    Region::new("PE", "Peru", 143507, "PEN", "es", LATAM),
+    Region::new("KN", "St. Kitts & Nevis", 143548, "USD", "en", LATAM),
+    Region::new("LC", "St. Lucia", 143549, "USD", "en", LATAM),
+    Region::new("VC", "St. Vincent & The Grenadines", 143550, "USD", "en", LATAM),
+    Region::new("SR", "Suriname", 143554, "USD", "en", LATAM),
+    Region::new("TT", "Trinidad & Tobago", 143551, "USD", "en", LATAM),
+    Region::new("TC", "Turks & Caicos", 143552, "USD", "en", LATAM),
+    Region::new("UY", "Uruguay", 143514, "USD", "es", LATAM),
+    Region::new("VE", "Venezuela", 143502, "USD", "es", LATAM),
+
+    // North America
+    Region::new("CA", "Canada", 143455, "CAD", "en", NORTH_AMERICA).excluding_tax(),
+    Region::new("US", "United States", 143441, "USD", "en", NORTH_AMERICA).excluding_tax(),
+
+    // Oceania
+    Region::new("FJ", "Fiji", 143583, "USD", "en", OCEANIA),
+    Region::new("FM", "Micronesia", 143591, "USD", "en", OCEANIA),
+    Region::new("NR", "Nauru", 143606, "USD", "en", OCEANIA),
+    Region::new("NZ", "New Zealand", 143461, "NZD", "en", OCEANIA),
+    Region::new("PG", "Papua New Guinea", 143597, "USD", "en", OCEANIA),
+    Region::new("SB", "Solomon Islands", 143601, "USD", "en", OCEANIA),
+    Region::new("TO", "Tonga", 143608, "USD", "en", OCEANIA),
+    Region::new("VU", "Vanuatu", 143609, "USD", "en", OCEANIA),
+];
+
+/// Look up a storefront by its (case-insensitive) region code.
+pub fn find_region(code: &str) -> Option<Region> {
+    REGIONS.iter().find(|r| r.code.eq_ignore_ascii_case(code)).copied()
+}
+
+/// Currencies whose home storefront can't be told from `REGIONS` alone:
+/// shared currencies, and currencies only used in storefronts that sell in
+/// US dollars.
+const CURRENCY_HOMES: &[(&str, &str)] = &[
+    ("EUR", "DE"),
+    ("USD", "US"),
+    ("CHF", "CH"),
+    ("AUD", "AU"),
+    ("XOF", "SN"),
+    ("XAF", "CM"),
+    ("XCD", "AG"),
+];
+
+/// The storefront most associated with `currency`: an explicit mapping for
+/// shared currencies, then the storefront that sells in it, then the region
+/// named by the currency code's first two letters (ISO 4217 convention).
+pub fn home_region(currency: &str) -> Option<Region> {
+    let currency = currency.trim().to_ascii_uppercase();
+    if let Some((_, code)) = CURRENCY_HOMES.iter().find(|(c, _)| *c == currency) {
+        return find_region(code);
+    }
+    REGIONS
+        .iter()
+        .find(|r| r.currency == currency)
+        .copied()
+        .or_else(|| currency.get(..2).and_then(find_region))
+}
+
+/// Which storefronts to fetch. Each kind of selector that is set narrows
+/// the selection (a storefront must match one of the `groups` *and* one of
+/// the `currencies`); `exclude` is applied last. Nothing set means every
+/// storefront.
+#[derive(Debug, Clone, Default)]
+pub struct RegionFilter {
+    /// Region codes, fetched in the given order.
+    pub codes: Vec<String>,
+    pub groups: Vec<RegionGroup>,
+    /// Storefront default currencies, e.g. `EUR`.
+    pub currencies: Vec<String>,
+    /// Region codes to leave out.
+    pub exclude: Vec<String>,
+}
+
+impl RegionFilter {
+    /// The matching storefronts. Unknown region codes and an empty result
+    /// are errors.
+    pub fn select(&self) -> Result<Vec<Region>> {
+        let lookup = |code: &String| find_region(code.trim()).ok_or_else(|| anyhow!("Unknown region code: {}", code));
+        let mut selected: Vec<Region> = if self.codes.is_empty() {
+            REGIONS.to_vec()
+        } else {
+            let mut regions = Vec::new();
+            for code in &self.codes {
+                let region = lookup(code)?;
+                if !regions.contains(&region) {
+                    regions.push(region);
+                }
+            }
+            regions
+        };
+        let excluded = self.exclude.iter().map(lookup).collect::<Result<Vec<_>>>()?;
+        selected.retain(|r| {
+            (self.groups.is_empty() || self.groups.contains(&r.group))
+                && (self.currencies.is_empty() || self.currencies.iter().any(|c| c.trim().eq_ignore_ascii_case(r.currency)))
+                && !excluded.contains(r)
+        });
+        if selected.is_empty() {
+            bail!("No regions match the selection");
+        }
+        Ok(selected)
+    }
+}
@@ -0,0 +1,114 @@
+//! Retrying transient HTTP failures with jittered exponential backoff.
+
+use std::sync::Arc;
+use std::time::Duration;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use crate::ratelimit::{host_of, RateLimiter};
+use crate::transport::{Response, Transport, TransportError};
+
+/// How hard to try before giving up on a request.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt; 0 disables retrying.
+    pub max_retries: u32,
+    /// Backoff before the first retry; doubled for each one after.
+    pub base_delay: Duration,
+    /// Upper bound on any single wait, including a server's `Retry-After`.
+    pub max_delay: Duration,
+    /// Limit on each individual attempt.
+    pub timeout: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            timeout: Duration::from_secs(20),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// "Full jitter" backoff: a random wait up to `base_delay * 2^attempt`.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let ceiling = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_delay);
+        ceiling.mul_f64(fastrand::f64())
+    }
+}
+
+/// Statuses worth another try: throttling and transient server trouble.
+fn is_retryable(status: u16) -> bool {
+    matches!(status, 408 | 429 | 500 | 502 | 503 | 504)
+}
+
+/// `Retry-After` as either delta-seconds or an HTTP date.
+fn retry_after(res: &Response) -> Option<Duration> {
+    let value = res.header("retry-after")?.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let at = DateTime::parse_from_rfc2822(value).ok()?.with_timezone(&Utc);
+    (at - Utc::now()).to_std().ok()
+}
+
+/// A [`Transport`] that times out each attempt and retries timeouts,
+/// connection failures and retryable statuses according to a [`RetryPolicy`].
+///
+/// When retries run out the last response (e.g. a 429) or error is returned
+/// as-is so the caller can tell what went wrong.
+pub struct Retrying {
+    inner: Arc<dyn Transport>,
+    policy: RetryPolicy,
+    limiter: Option<Arc<RateLimiter>>,
+}
+
+impl Retrying {
+    pub fn new(inner: Arc<dyn Transport>, policy: RetryPolicy) -> Self {
+        Retrying { inner, policy, limiter: None }
+    }
+
+    /// Wait for `limiter` before every attempt, retries included. The wait
+    /// doesn't count towards the attempt's timeout.
+    pub fn with_limiter(mut self, limiter: Arc<RateLimiter>) -> Self {
+        self.limiter = Some(limiter);
+        self
+    }
+}
+
+#[async_trait]
+impl Transport for Retrying {
+    async fn get(&self, url: &str) -> Result<Response, TransportError> {
+        self.get_with_headers(url, &[]).await
+    }
+
+    async fn get_with_headers(&self, url: &str, headers: &[(&str, &str)]) -> Result<Response, TransportError> {
+        let mut attempt = 0;
+        let host = host_of(url);
+        loop {
+            if let Some(limiter) = &self.limiter {
+                limiter.acquire(&host).await;
+            }
+            let result = tokio::time::timeout(self.policy.timeout, self.inner.get_with_headers(url, headers))
+                .await
+                .unwrap_or(Err(TransportError::Timeout));
+            let wait = match &result {
+                Ok(res) if is_retryable(res.status) => {
+                    retry_after(res).map_or_else(|| self.policy.backoff(attempt), |d| d.min(self.policy.max_delay))
+                }
+                Ok(_) => return result,
+                Err(_) => self.policy.backoff(attempt),
+            };
+            if attempt >= self.policy.max_retries {
+                return result;
+            }
+            tokio::time::sleep(wait).await;
+            attempt += 1;
+        }
+    }
+}
@@ -0,0 +1,152 @@
+//! Typed view of the `shoebox-media-api-cache-apps` JSON embedded in storefront pages.
+//!
+//! Only the fields this crate reads are modelled. Fields a price depends on are
+//! required, so a schema change surfaces as a parse error rather than as a
+//! silent `0.0`.
+
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::Value;
+use crate::pricing::RegionError;
+
+/// The `apps` resource for the page's app.
+#[derive(Deserialize, Debug, Clone)]
+pub(crate) struct AppResource {
+    #[serde(default)]
+    pub attributes: AppAttributes,
+    #[serde(default)]
+    pub relationships: AppRelationships,
+}
+
+/// Attributes of the app itself. Paid-app prices aren't always present here,
+/// which is why they stay optional and the HTML fallbacks exist.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct AppAttributes {
+    pub price: Option<f64>,
+    pub currency_code: Option<String>,
+    pub formatted_price: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+pub(crate) struct AppRelationships {
+    #[serde(rename = "top-in-apps")]
+    pub top_in_apps: Option<Relationship<InAppResource>>,
+}
+
+/// A relationship's items. Long ones are paginated: `next` is the
+/// catalog-API path of the following page.
+#[derive(Deserialize, Debug, Clone)]
+pub(crate) struct Relationship<T> {
+    pub data: Vec<T>,
+    #[serde(default)]
+    pub next: Option<String>,
+}
+
+/// One entry of `relationships.top-in-apps.data`.
+#[derive(Deserialize, Debug, Clone)]
+pub(crate) struct InAppResource {
+    pub id: String,
+    pub attributes: InAppAttributes,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct InAppAttributes {
+    pub name: String,
+    /// Developer-assigned product identifier.
+    pub offer_name: String,
+    /// `Consumable`, `Non-Consumable`, `Auto-Renewable Subscription` or
+    /// `Non-Renewing Subscription`.
+    pub kind: Option<String>,
+    #[serde(default)]
+    pub offers: Vec<Offer>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Offer {
+    pub price: f64,
+    pub currency_code: String,
+    pub price_formatted: Option<String>,
+    /// ISO 8601 billing period of a subscription, e.g. `P1M`.
+    pub recurring_subscription_period: Option<String>,
+    /// Introductory offers; Apple lists at most one per subscription.
+    #[serde(default)]
+    pub discounts: Vec<Discount>,
+}
+
+/// A subscription's introductory offer.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Discount {
+    /// `FreeTrial`, `PayAsYouGo` or `PayUpFront`.
+    pub mode_type: String,
+    pub price: Option<f64>,
+    pub num_of_periods: Option<u32>,
+    pub recurring_subscription_period: Option<String>,
+}
+
+impl AppResource {
+    /// The app's top in-app purchases, or an empty slice if it lists none.
+    pub fn top_in_apps(&self) -> &[InAppResource] {
+        self.relationships
+            .top_in_apps
+            .as_ref()
+            .map_or(&[], |r| r.data.as_slice())
+    }
+
+    /// Catalog-API path of the IAPs after the shortlist, if Apple has more.
+    pub fn top_in_apps_next(&self) -> Option<&str> {
+        self.relationships.top_in_apps.as_ref()?.next.as_deref()
+    }
+}
+
+impl InAppResource {
+    /// The IAP's current offer; every listed IAP is expected to have one.
+    pub fn offer(&self) -> Result<&Offer, RegionError> {
+        self.attributes.offers.first().ok_or_else(|| {
+            RegionError::Parse(format!("in-app purchase {} has no offers", self.attributes.offer_name))
+        })
+    }
+}
+
+/// Pull the app resource out of the page's `shoebox-media-api-cache-apps` script.
+///
+/// The script holds a map of API URLs to JSON-encoded responses (the app
+/// itself, its reviews, ...). The entry carrying `top-in-apps` is preferred,
+/// then any `apps` resource, then the first decodable one.
+pub(crate) fn parse_app_resource(html: &str) -> Result<AppResource, RegionError> {
+    let re = Regex::new(
+        r#"<script[^>]*id="shoebox-media-api-cache-apps"[^>]*>([\s\S]*?)</script>"#
+    ).unwrap();
+    let caps = re
+        .captures(html)
+        .ok_or_else(|| RegionError::Parse("Unable to find App Store cache script in HTML".into()))?;
+    let outer: Value = serde_json::from_str(caps[1].trim())
+        .map_err(|e| RegionError::Parse(format!("invalid cache script JSON: {}", e)))?;
+    let entries: Vec<Value> = outer
+        .as_object()
+        .map(|map| {
+            map.values()
+                .filter_map(|v| v.as_str())
+                .filter_map(|s| serde_json::from_str::<Value>(s).ok())
+                .map(|mut val| val["d"][0].take())
+                .filter(|d| !d.is_null())
+                .collect()
+        })
+        .unwrap_or_default();
+    let app = entries
+        .iter()
+        .find(|d| d["relationships"]["top-in-apps"]["data"].is_array())
+        .or_else(|| entries.iter().find(|d| d["type"] == "apps"))
+        .or_else(|| entries.first())
+        .ok_or_else(|| RegionError::Parse("Failed to extract App Store JSON data".into()))?;
+    AppResource::deserialize(app)
+        .map_err(|e| RegionError::Parse(format!("unexpected app resource shape: {}", e)))
+}
+
+/// One page of a paginated relationship as returned by the catalog API.
+pub(crate) fn parse_relationship_page(body: &str) -> Result<Relationship<InAppResource>, RegionError> {
+    serde_json::from_str(body).map_err(|e| RegionError::Parse(format!("unexpected catalog page shape: {}", e)))
+}
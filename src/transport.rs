@@ -0,0 +1,169 @@
+//! The HTTP layer, behind a trait so storefront and exchange-rate requests can
+//! be served by something other than the real endpoints.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::PathBuf;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use reqwest::header::{HeaderMap, HeaderValue, ACCEPT_LANGUAGE};
+
+/// Status, headers and body of a completed GET request.
+#[derive(Debug, Clone, Default)]
+pub struct Response {
+    pub status: u16,
+    /// Header names are lowercased.
+    pub headers: HashMap<String, String>,
+    pub body: String,
+}
+
+impl Response {
+    pub fn new(status: u16, body: impl Into<String>) -> Self {
+        Response { status, headers: HashMap::new(), body: body.into() }
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(&name.to_ascii_lowercase()).map(String::as_str)
+    }
+
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+}
+
+/// A request that never produced a response.
+#[derive(Debug, Clone)]
+pub enum TransportError {
+    /// No response within the per-request timeout.
+    Timeout,
+    /// Connection, TLS or protocol failure.
+    Request(String),
+}
+
+impl fmt::Display for TransportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransportError::Timeout => f.write_str("request timed out"),
+            TransportError::Request(msg) => f.write_str(msg),
+        }
+    }
+}
+
+impl std::error::Error for TransportError {}
+
+/// Performs the GET requests the crate needs. Non-success statuses are
+/// returned as responses; `Err` is for requests that never got an answer.
+#[async_trait]
+pub trait Transport: Send + Sync {
+    async fn get(&self, url: &str) -> Result<Response, TransportError>;
+
+    /// GET with extra request headers, e.g. the catalog API's bearer token.
+    /// Transports that can't send headers ignore them.
+    async fn get_with_headers(&self, url: &str, headers: &[(&str, &str)]) -> Result<Response, TransportError> {
+        let _ = headers;
+        self.get(url).await
+    }
+}
+
+/// Settings for the shared `reqwest` client.
+#[derive(Debug, Clone)]
+pub struct HttpConfig {
+    pub user_agent: String,
+    /// Sent as `Accept-Language`, e.g. `en-US,en;q=0.9`.
+    pub accept_language: Option<String>,
+    /// `http://`, `https://` or `socks5://` proxy for every request. When
+    /// unset, the usual `HTTP(S)_PROXY` environment variables apply.
+    pub proxy: Option<String>,
+    /// Extra PEM root certificate to trust, e.g. for an intercepting proxy.
+    pub ca_cert: Option<PathBuf>,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        HttpConfig {
+            user_agent: concat!("appstore_pricing/", env!("CARGO_PKG_VERSION")).to_string(),
+            accept_language: None,
+            proxy: None,
+            ca_cert: None,
+        }
+    }
+}
+
+impl HttpConfig {
+    /// A `reqwest` client with these settings, gzip and brotli enabled.
+    pub fn client(&self) -> Result<reqwest::Client> {
+        let mut headers = HeaderMap::new();
+        if let Some(lang) = &self.accept_language {
+            headers.insert(
+                ACCEPT_LANGUAGE,
+                HeaderValue::from_str(lang).context("Invalid Accept-Language value")?,
+            );
+        }
+        let mut builder = reqwest::Client::builder()
+            .user_agent(&self.user_agent)
+            .default_headers(headers)
+            .gzip(true)
+            .brotli(true);
+        if let Some(proxy) = &self.proxy {
+            builder = builder.proxy(reqwest::Proxy::all(proxy).context("Invalid proxy URL")?);
+        }
+        if let Some(path) = &self.ca_cert {
+            let pem = std::fs::read(path)
+                .with_context(|| format!("Unable to read CA certificate {}", path.display()))?;
+            let cert = reqwest::Certificate::from_pem(&pem)
+                .with_context(|| format!("Invalid PEM certificate {}", path.display()))?;
+            builder = builder.add_root_certificate(cert);
+        }
+        builder.build().context("Unable to build HTTP client")
+    }
+}
+
+/// The default transport: one pooled `reqwest::Client` shared by every
+/// storefront and exchange-rate request, with gzip and brotli enabled.
+#[derive(Debug, Clone)]
+pub struct ReqwestTransport {
+    client: reqwest::Client,
+}
+
+impl ReqwestTransport {
+    pub fn new(config: &HttpConfig) -> Result<Self> {
+        Ok(ReqwestTransport { client: config.client()? })
+    }
+}
+
+impl Default for ReqwestTransport {
+    fn default() -> Self {
+        ReqwestTransport::new(&HttpConfig::default()).expect("default HTTP client configuration is valid")
+    }
+}
+
+fn request_error(e: reqwest::Error) -> TransportError {
+    if e.is_timeout() {
+        TransportError::Timeout
+    } else {
+        TransportError::Request(e.to_string())
+    }
+}
+
+#[async_trait]
+impl Transport for ReqwestTransport {
+    async fn get(&self, url: &str) -> Result<Response, TransportError> {
+        self.get_with_headers(url, &[]).await
+    }
+
+    async fn get_with_headers(&self, url: &str, headers: &[(&str, &str)]) -> Result<Response, TransportError> {
+        let mut req = self.client.get(url);
+        for (name, value) in headers {
+            req = req.header(*name, *value);
+        }
+        let res = req.send().await.map_err(request_error)?;
+        let status = res.status().as_u16();
+        let headers = res
+            .headers()
+            .iter()
+            .filter_map(|(k, v)| Some((k.as_str().to_string(), v.to_str().ok()?.to_string())))
+            .collect();
+        let body = res.text().await.map_err(request_error)?;
+        Ok(Response { status, headers, body })
+    }
+}
@@ -0,0 +1,79 @@
+use appstore_pricing::batch::{parse_batch_csv, parse_batch_text, parse_batch_toml, read_batch_file, BatchEntry};
+
+fn entry(app: &str, iaps: &[&str], regions: &[&str]) -> BatchEntry {
+    BatchEntry {
+        app: app.to_string(),
+        iaps: iaps.iter().map(|s| s.to_string()).collect(),
+        regions: regions.iter().map(|s| s.to_string()).collect(),
+    }
+}
+
+#[test]
+fn text_lists_apps_products_and_regions() {
+    let list = "\
+# competitors
+1234567890
+1234567890@com.example.pro,com.example.plus  US,JP   # the big two
+https://apps.apple.com/gb/app/pocket-ledger/id42 GB
+
+";
+    assert_eq!(
+        parse_batch_text(list).unwrap(),
+        [
+            entry("1234567890", &[], &[]),
+            entry("1234567890", &["com.example.pro", "com.example.plus"], &["US", "JP"]),
+            entry("https://apps.apple.com/gb/app/pocket-ledger/id42", &[], &["GB"]),
+        ]
+    );
+    let err = parse_batch_text("42 US GB").unwrap_err();
+    assert!(err.to_string().contains("Line 1"), "{}", err);
+}
+
+#[test]
+fn csv_columns_are_found_by_name() {
+    let csv = "regions,app,iaps\nUS;JP,1234567890,com.example.pro\n,42,\n";
+    assert_eq!(
+        parse_batch_csv(csv).unwrap(),
+        [entry("1234567890", &["com.example.pro"], &["US", "JP"]), entry("42", &[], &[])]
+    );
+    assert_eq!(parse_batch_csv("app\n42\n").unwrap(), [entry("42", &[], &[])]);
+    assert!(parse_batch_csv("id,regions\n42,US\n").unwrap_err().to_string().contains("no `app` column"));
+    assert!(parse_batch_csv("app,regions\n,US\n").unwrap_err().to_string().contains("Row 2 has no app"));
+}
+
+#[test]
+fn toml_has_a_table_per_app() {
+    let toml = r#"
+[[apps]]
+app = "1234567890"
+iaps = ["com.example.pro"]
+regions = ["US", "JP"]
+
+[[apps]]
+app = "42"
+"#;
+    assert_eq!(
+        parse_batch_toml(toml).unwrap(),
+        [entry("1234567890", &["com.example.pro"], &["US", "JP"]), entry("42", &[], &[])]
+    );
+    // Typos are caught rather than silently pricing everything.
+    assert!(parse_batch_toml("[[apps]]\napp = \"42\"\nregion = [\"US\"]\n").is_err());
+}
+
+#[test]
+fn file_format_follows_the_extension() {
+    let dir = tempfile::tempdir().unwrap();
+    let write = |name: &str, contents: &str| {
+        let path = dir.path().join(name);
+        std::fs::write(&path, contents).unwrap();
+        path
+    };
+    let expected = [entry("42", &[], &["GB"])];
+    assert_eq!(read_batch_file(&write("apps.txt", "42 GB\n")).unwrap(), expected);
+    assert_eq!(read_batch_file(&write("apps.CSV", "app,regions\n42,GB\n")).unwrap(), expected);
+    assert_eq!(read_batch_file(&write("apps.toml", "[[apps]]\napp = \"42\"\nregions = [\"GB\"]\n")).unwrap(), expected);
+
+    let err = read_batch_file(&write("empty.txt", "# nothing yet\n")).unwrap_err();
+    assert!(err.to_string().contains("lists no apps"), "{}", err);
+    assert!(read_batch_file(&dir.path().join("missing.txt")).is_err());
+}
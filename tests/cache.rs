@@ -0,0 +1,77 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use appstore_pricing::cache::{Cache, CacheMode, Cached};
+use appstore_pricing::transport::{Response, Transport, TransportError};
+use async_trait::async_trait;
+
+/// Answers every URL with a fixed status and counts the calls.
+struct Counting {
+    status: u16,
+    calls: Mutex<usize>,
+}
+
+impl Counting {
+    fn new(status: u16) -> Arc<Self> {
+        Arc::new(Counting { status, calls: Mutex::new(0) })
+    }
+
+    fn calls(&self) -> usize {
+        *self.calls.lock().unwrap()
+    }
+}
+
+#[async_trait]
+impl Transport for Counting {
+    async fn get(&self, url: &str) -> Result<Response, TransportError> {
+        *self.calls.lock().unwrap() += 1;
+        Ok(Response::new(self.status, format!("{} #{}", url, self.calls())))
+    }
+}
+
+const URL: &str = "https://apps.apple.com/gb/app/id1234567890";
+
+#[tokio::test]
+async fn serves_fresh_entries_and_refetches_stale_ones() {
+    let dir = tempfile::tempdir().unwrap();
+    let inner = Counting::new(200);
+    let cached = Cached::new(inner.clone(), Cache::new(dir.path()));
+    assert_eq!(cached.get(URL).await.unwrap().body, format!("{} #1", URL));
+    assert_eq!(cached.get(URL).await.unwrap().body, format!("{} #1", URL));
+    assert_eq!(inner.calls(), 1);
+    cached.get("https://apps.apple.com/jp/app/id1234567890").await.unwrap();
+    assert_eq!(inner.calls(), 2);
+
+    let stale = Cached::new(inner.clone(), Cache { ttl: Duration::ZERO, ..Cache::new(dir.path()) });
+    std::thread::sleep(Duration::from_millis(5));
+    assert_eq!(stale.get(URL).await.unwrap().body, format!("{} #3", URL));
+    assert_eq!(inner.calls(), 3);
+}
+
+#[tokio::test]
+async fn offline_uses_stale_entries_and_fails_on_misses() {
+    let dir = tempfile::tempdir().unwrap();
+    let inner = Counting::new(404);
+    Cached::new(inner.clone(), Cache::new(dir.path())).get(URL).await.unwrap();
+
+    let offline = Cache { ttl: Duration::ZERO, mode: CacheMode::Offline, ..Cache::new(dir.path()) };
+    let cached = Cached::new(inner.clone(), offline);
+    assert_eq!(cached.get(URL).await.unwrap().status, 404);
+    assert!(cached.get("https://apps.apple.com/fr/app/id1234567890").await.is_err());
+    assert_eq!(inner.calls(), 1);
+}
+
+#[tokio::test]
+async fn refresh_refetches_and_errors_are_not_kept() {
+    let dir = tempfile::tempdir().unwrap();
+    let failing = Counting::new(503);
+    let cached = Cached::new(failing.clone(), Cache::new(dir.path()));
+    cached.get(URL).await.unwrap();
+    cached.get(URL).await.unwrap();
+    assert_eq!(failing.calls(), 2);
+
+    let inner = Counting::new(200);
+    Cached::new(inner.clone(), Cache::new(dir.path())).get(URL).await.unwrap();
+    let refresh = Cached::new(inner.clone(), Cache { mode: CacheMode::Refresh, ..Cache::new(dir.path()) });
+    assert_eq!(refresh.get(URL).await.unwrap().body, format!("{} #2", URL));
+    assert_eq!(Cache::new(dir.path()).lookup(URL).unwrap().0.body, format!("{} #2", URL));
+}
@@ -0,0 +1,125 @@
+//! A minimal HTTP/1.1 stand-in for apps.apple.com, the exchange-rate API and
+//! webhook receivers.
+//!
+//! Routes map a request path (including any query string) to a canned status
+//! and body; anything else gets a 404. Every request is recorded, with its
+//! body, so tests can assert on what was fetched or posted.
+
+#![allow(dead_code)]
+
+pub mod smtp;
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+#[derive(Debug, Clone)]
+pub struct Recorded {
+    pub method: String,
+    pub path: String,
+    pub headers: HashMap<String, String>,
+    pub body: String,
+}
+
+#[derive(Clone, Default)]
+struct State {
+    routes: Arc<Mutex<HashMap<String, (u16, String)>>>,
+    requests: Arc<Mutex<Vec<Recorded>>>,
+}
+
+pub struct MockServer {
+    pub url: String,
+    state: State,
+}
+
+impl MockServer {
+    pub fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let state = State::default();
+        let shared = state.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let state = shared.clone();
+                thread::spawn(move || handle(stream, state));
+            }
+        });
+        MockServer { url, state }
+    }
+
+    pub fn route(&self, path: &str, status: u16, body: impl Into<String>) -> &Self {
+        self.state.routes.lock().unwrap().insert(path.to_string(), (status, body.into()));
+        self
+    }
+
+    pub fn fixture(&self, path: &str, name: &str) -> &Self {
+        let file = format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name);
+        self.route(path, 200, std::fs::read_to_string(file).unwrap())
+    }
+
+    pub fn requests(&self) -> Vec<Recorded> {
+        self.state.requests.lock().unwrap().clone()
+    }
+
+    pub fn hits(&self, path: &str) -> usize {
+        self.requests().iter().filter(|r| r.path == path).count()
+    }
+
+    /// Bodies POSTed to `path`, oldest first.
+    pub fn posted(&self, path: &str) -> Vec<String> {
+        self.requests().into_iter().filter(|r| r.method == "POST" && r.path == path).map(|r| r.body).collect()
+    }
+}
+
+fn handle(stream: TcpStream, state: State) {
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    let mut request_line = String::new();
+    if reader.read_line(&mut request_line).is_err() {
+        return;
+    }
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or("GET").to_string();
+    let path = parts.next().unwrap_or("/").to_string();
+    let mut headers = HashMap::new();
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).unwrap_or(0) == 0 || line == "\r\n" {
+            break;
+        }
+        if let Some((k, v)) = line.split_once(':') {
+            headers.insert(k.trim().to_ascii_lowercase(), v.trim().to_string());
+        }
+    }
+    let length = headers.get("content-length").and_then(|l| l.parse().ok()).unwrap_or(0);
+    let mut body = vec![0; length];
+    if reader.read_exact(&mut body).is_err() {
+        return;
+    }
+    let body = String::from_utf8_lossy(&body).into_owned();
+    state.requests.lock().unwrap().push(Recorded { method, path: path.clone(), headers, body });
+
+    let (status, body) = state
+        .routes
+        .lock()
+        .unwrap()
+        .get(&path)
+        .cloned()
+        .unwrap_or((404, "<html><body>Not Found</body></html>".to_string()));
+    let mut stream = stream;
+    let _ = write!(
+        stream,
+        "HTTP/1.1 {} Mock\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+}
+
+/// An open.er-api.com style response body.
+pub fn fx_body(base: &str, rates: &[(&str, f64)]) -> String {
+    let rates: serde_json::Map<String, serde_json::Value> =
+        rates.iter().map(|(k, v)| (k.to_string(), (*v).into())).collect();
+    serde_json::json!({ "result": "success", "base_code": base, "rates": rates }).to_string()
+}
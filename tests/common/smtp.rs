@@ -0,0 +1,82 @@
+//! A minimal SMTP stand-in that accepts every message and keeps it.
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+#[derive(Debug, Clone, Default)]
+pub struct Mail {
+    pub from: String,
+    pub to: Vec<String>,
+    /// Headers and body, as sent after `DATA`.
+    pub data: String,
+}
+
+pub struct SmtpServer {
+    pub url: String,
+    mail: Arc<Mutex<Vec<Mail>>>,
+}
+
+impl SmtpServer {
+    pub fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("smtp://{}", listener.local_addr().unwrap());
+        let mail = Arc::new(Mutex::new(Vec::new()));
+        let shared = mail.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let mail = shared.clone();
+                thread::spawn(move || session(stream, mail));
+            }
+        });
+        SmtpServer { url, mail }
+    }
+
+    pub fn received(&self) -> Vec<Mail> {
+        self.mail.lock().unwrap().clone()
+    }
+}
+
+fn session(stream: TcpStream, mail: Arc<Mutex<Vec<Mail>>>) {
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    let mut out = stream;
+    let mut current = Mail::default();
+    let _ = out.write_all(b"220 mock ESMTP\r\n");
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).unwrap_or(0) == 0 {
+            return;
+        }
+        let command = line.trim_end().to_ascii_uppercase();
+        let reply: &[u8] = if command.starts_with("EHLO") || command.starts_with("HELO") {
+            b"250 mock\r\n"
+        } else if command.starts_with("MAIL FROM:") {
+            current.from = line.trim_end()[10..].trim_matches(['<', '>', ' ']).to_string();
+            b"250 OK\r\n"
+        } else if command.starts_with("RCPT TO:") {
+            current.to.push(line.trim_end()[8..].trim_matches(['<', '>', ' ']).to_string());
+            b"250 OK\r\n"
+        } else if command == "DATA" {
+            let _ = out.write_all(b"354 End data with <CR><LF>.<CR><LF>\r\n");
+            loop {
+                let mut data = String::new();
+                if reader.read_line(&mut data).unwrap_or(0) == 0 {
+                    return;
+                }
+                if data == ".\r\n" {
+                    break;
+                }
+                current.data += &data;
+            }
+            mail.lock().unwrap().push(std::mem::take(&mut current));
+            b"250 OK\r\n"
+        } else if command == "QUIT" {
+            let _ = out.write_all(b"221 Bye\r\n");
+            return;
+        } else {
+            b"250 OK\r\n"
+        };
+        let _ = out.write_all(reply);
+    }
+}
@@ -0,0 +1,52 @@
+use appstore_pricing::diff::{diff_prices, ChangeKind};
+use appstore_pricing::{find_region, Pricing};
+
+fn price(code: &str, amount: f64, currency: &str, converted: f64) -> Pricing {
+    let mut p = Pricing::new(&find_region(code).unwrap(), amount, currency).for_product("pro");
+    p.converted_amount = Some(converted);
+    p
+}
+
+#[test]
+fn reports_added_removed_and_changed_regions() {
+    let before = [price("US", 4.99, "USD", 4.99), price("JP", 800.0, "JPY", 5.33), price("GB", 4.49, "GBP", 5.61)];
+    let after = [price("US", 4.99, "USD", 4.99), price("JP", 900.0, "JPY", 6.0), price("DE", 5.49, "EUR", 5.93)];
+    let changes = diff_prices(&before, &after);
+    let summary: Vec<_> = changes.iter().map(|c| (c.region_code.as_str(), c.change)).collect();
+    assert_eq!(summary, [("JP", ChangeKind::Changed), ("DE", ChangeKind::Added), ("GB", ChangeKind::Removed)]);
+
+    let japan = &changes[0];
+    assert_eq!((japan.old_amount, japan.new_amount), (Some(800.0), Some(900.0)));
+    assert_eq!(japan.amount_change_pct.map(|p| (p * 100.0).round() / 100.0), Some(12.5));
+    assert_eq!(japan.converted_change_pct.map(|p| (p * 10.0).round() / 10.0), Some(12.6));
+    assert_eq!((changes[1].old_amount, changes[1].amount_change_pct), (None, None));
+    assert_eq!((changes[2].new_amount, changes[2].old_currency.as_deref()), (None, Some("GBP")));
+}
+
+#[test]
+fn exchange_rate_moves_alone_are_not_changes() {
+    let before = [price("JP", 800.0, "JPY", 5.33)];
+    let after = [price("JP", 800.0, "JPY", 5.10)];
+    assert!(diff_prices(&before, &after).is_empty());
+}
+
+#[test]
+fn currency_switches_are_changes_without_a_local_percentage() {
+    let before = [price("VE", 4.99, "VES", 4.99)];
+    let after = [price("VE", 4.99, "USD", 4.99)];
+    let changes = diff_prices(&before, &after);
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].amount_change_pct, None);
+    assert_eq!(changes[0].converted_change_pct, Some(0.0));
+}
+
+#[test]
+fn products_are_compared_separately() {
+    let mut coins = price("US", 0.99, "USD", 0.99);
+    coins.product_id = Some("coins".into());
+    let before = [price("US", 4.99, "USD", 4.99), coins.clone()];
+    coins.amount = 1.99;
+    let changes = diff_prices(&before, &[price("US", 4.99, "USD", 4.99), coins]);
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].product_id.as_deref(), Some("coins"));
+}
@@ -0,0 +1,701 @@
+//! The whole CLI flow against a local stand-in for the App Store and FX API.
+
+mod common;
+
+use std::process::{Command, Output};
+use appstore_pricing::{find_region, PricingClient, RegionOutcome};
+use common::{fx_body, MockServer};
+use futures::StreamExt;
+use serde_json::Value;
+
+const APP: &str = "/us/app/id1234567890";
+
+fn storefront() -> MockServer {
+    let server = MockServer::start();
+    server
+        .fixture(APP, "iap_app.html")
+        .fixture("/jp/app/id1234567890", "iap_app_jp.html")
+        .route("/fx/USD", 200, fx_body("USD", &[("USD", 1.0), ("JPY", 150.0), ("GBP", 0.8)]));
+    server
+}
+
+/// `iap_app.html` without Pro Monthly, whose price tier the coin pack has.
+fn without_pro_monthly() -> String {
+    let body = std::fs::read_to_string(format!("{}/tests/fixtures/iap_app.html", env!("CARGO_MANIFEST_DIR"))).unwrap();
+    let (start, end) = (body.find(r#"{\"id\": \"1500000002\""#).unwrap(), body.find(r#"{\"id\": \"1500000003\""#).unwrap());
+    format!("{}{}", &body[..start], &body[end..])
+}
+
+/// The binary, kept away from the user's response cache and price history.
+fn bin() -> Command {
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_appstore_pricing"));
+    cmd.args(["--no-cache", "--no-history"]);
+    cmd
+}
+
+fn run(server: &MockServer, args: &[&str]) -> Output {
+    bin()
+        .args(["1234567890", "--currency", "USD", "--yes"])
+        .args(["--store-url", &server.url])
+        .args(["--fx-url", &format!("{}/fx", server.url)])
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn prices_selected_iap_across_regions_as_json() {
+    let server = storefront();
+    let out = run(&server, &["--iap", "com.ledgerlabs.pocketledger.removeads", "--regions", "US,JP,GB", "--format", "json"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+
+    let rows: Vec<Value> = serde_json::from_slice(&out.stdout).unwrap();
+    let summary: Vec<_> = rows
+        .iter()
+        .map(|r| (r["region_code"].as_str().unwrap(), r["amount"].as_f64().unwrap(), r["converted_amount"].as_f64().unwrap()))
+        .collect();
+    assert_eq!(summary, [("US", 2.99, 2.99), ("JP", 500.0, 3.33)]);
+    assert_eq!(rows[1]["base_currency"], "USD");
+    assert_eq!(rows[1]["rate"], 150.0);
+
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(stderr.contains("Priced 2 of 3 regions"), "{}", stderr);
+    assert!(server.hits("/gb/app/id1234567890") > 0);
+}
+
+#[test]
+fn csv_output_has_stable_header() {
+    let server = storefront();
+    let out = run(&server, &["--iap", "2", "--regions", "US", "--format", "csv"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    let stdout = String::from_utf8(out.stdout).unwrap();
+    let mut lines = stdout.lines();
+    assert_eq!(
+        lines.next(),
+        Some("region_code,region_name,amount,currency,converted_amount,base_currency,rate,fetched_at,product_id,match_note,catalog_complete,offer_type,billing_period,intro_offer,intro_price,intro_period,intro_periods,converted_monthly")
+    );
+    let row = lines.next().unwrap();
+    assert!(row.starts_with("US,United States,4.99,USD,4.99,USD,1,"), "{}", row);
+    assert!(row.ends_with(",com.ledgerlabs.pocketledger.pro.monthly,,true,consumable,,,,,,"), "{}", row);
+}
+
+#[test]
+fn markdown_matrix_has_one_column_per_iap() {
+    let server = storefront();
+    let out = run(&server, &["--all-iaps", "--regions", "US,JP", "--format", "markdown"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    assert_eq!(
+        String::from_utf8(out.stdout).unwrap(),
+        "| Region | Remove Ads | Pro Monthly | Coin Pack — 500 |\n\
+         |---|---|---|---|\n\
+         | Japan | ¥500 ($3.33) | ¥800 ($5.33) | — |\n\
+         | United States | $2.99 ($2.99) | $4.99 ($4.99) | $9.99 ($9.99) |\n"
+    );
+}
+
+#[test]
+fn all_iaps_fetches_each_storefront_once() {
+    let server = storefront();
+    let out = run(&server, &["--all-iaps", "--regions", "US,JP", "--format", "json"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+
+    let rows: Vec<Value> = serde_json::from_slice(&out.stdout).unwrap();
+    let mut cells: Vec<_> = rows
+        .iter()
+        .map(|r| (r["region_code"].as_str().unwrap(), r["product_id"].as_str().unwrap(), r["amount"].as_f64().unwrap()))
+        .collect();
+    cells.sort_by(|a, b| (a.0, a.1).cmp(&(b.0, b.1)));
+    assert_eq!(
+        cells,
+        [
+            ("JP", "com.ledgerlabs.pocketledger.pro.monthly", 800.0),
+            ("JP", "com.ledgerlabs.pocketledger.removeads", 500.0),
+            ("US", "com.ledgerlabs.pocketledger.coins500", 9.99),
+            ("US", "com.ledgerlabs.pocketledger.pro.monthly", 4.99),
+            ("US", "com.ledgerlabs.pocketledger.removeads", 2.99),
+        ]
+    );
+    // Every IAP's price comes out of the same page fetch.
+    assert_eq!(server.hits("/jp/app/id1234567890"), 1);
+}
+
+#[test]
+fn all_iaps_table_is_a_region_by_iap_matrix() {
+    let server = storefront();
+    let out = run(&server, &["--all-iaps", "--regions", "US,JP"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+
+    let stdout = String::from_utf8(out.stdout).unwrap();
+    let header = stdout.lines().find(|l| l.contains("Region")).unwrap();
+    let columns: Vec<&str> = header.split('|').map(str::trim).filter(|c| !c.is_empty()).collect();
+    assert_eq!(columns, ["Region", "Remove Ads", "Pro Monthly", "Coin Pack — 500"]);
+    let japan = stdout.lines().find(|l| l.contains("Japan")).unwrap();
+    assert!(japan.contains("¥500 ($3.33)"), "{}", japan);
+    assert!(japan.contains("—"), "{}", japan);
+}
+
+#[test]
+fn fuzzy_iap_match_is_noted() {
+    let server = storefront();
+    server.fixture("/de/app/id1234567890", "iap_app_renamed.html");
+    let out = run(&server, &["--iap", "Pro Monthly", "--regions", "US,DE", "--format", "json"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+
+    let rows: Vec<Value> = serde_json::from_slice(&out.stdout).unwrap();
+    let germany = rows.iter().find(|r| r["region_code"] == "DE").unwrap();
+    assert_eq!(germany["amount"], 5.49);
+    assert_eq!(germany["match_note"], "matched by name similarity to \"Pro-Monthly\"");
+    assert!(String::from_utf8_lossy(&out.stderr).contains("matched by name similarity"));
+}
+
+#[test]
+fn full_catalog_follows_next_links() {
+    let server = MockServer::start();
+    server
+        .fixture(APP, "iap_app_paged.html")
+        .fixture("/v1/catalog/us/apps/1234567890/top-in-apps?offset=3", "iap_catalog_page2.json")
+        .route("/fx/USD", 200, fx_body("USD", &[("USD", 1.0)]));
+
+    let shortlist = run(&server, &["--all-iaps", "--regions", "US", "--format", "json"]);
+    assert!(shortlist.status.success(), "{}", String::from_utf8_lossy(&shortlist.stderr));
+    let rows: Vec<Value> = serde_json::from_slice(&shortlist.stdout).unwrap();
+    assert_eq!(rows.len(), 3);
+    assert!(rows.iter().all(|r| r["catalog_complete"] == false));
+    assert!(String::from_utf8_lossy(&shortlist.stderr).contains("use --full-catalog"));
+
+    let full = run(&server, &["--all-iaps", "--full-catalog", "--catalog-url", &server.url, "--regions", "US", "--format", "json"]);
+    assert!(full.status.success(), "{}", String::from_utf8_lossy(&full.stderr));
+    let rows: Vec<Value> = serde_json::from_slice(&full.stdout).unwrap();
+    assert_eq!(rows.len(), 5);
+    assert!(rows.iter().all(|r| r["catalog_complete"] == true));
+    assert!(rows.iter().any(|r| r["product_id"] == "com.ledgerlabs.pocketledger.pro.yearly"));
+    let page2 = server.requests().into_iter().find(|r| r.path.starts_with("/v1/catalog/")).unwrap();
+    assert_eq!(page2.headers.get("authorization").map(String::as_str), Some("Bearer fixture-token"));
+}
+
+#[test]
+fn failed_catalog_page_leaves_the_listing_incomplete() {
+    let server = MockServer::start();
+    server
+        .fixture(APP, "iap_app_paged.html")
+        .route("/v1/catalog/us/apps/1234567890/top-in-apps?offset=3", 500, "")
+        .route("/fx/USD", 200, fx_body("USD", &[("USD", 1.0)]));
+
+    let out = run(&server, &["--all-iaps", "--full-catalog", "--catalog-url", &server.url, "--regions", "US", "--format", "json"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    let rows: Vec<Value> = serde_json::from_slice(&out.stdout).unwrap();
+    assert_eq!(rows.len(), 3);
+    assert!(rows.iter().all(|r| r["catalog_complete"] == false));
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(stderr.contains("the rest couldn't be fetched (catalog page answered HTTP 500)"), "{}", stderr);
+}
+
+#[test]
+fn subscription_table_shows_monthly_price() {
+    let server = storefront();
+    server.fixture(APP, "iap_subscriptions.html");
+    let out = run(&server, &["--iap", "Pro Yearly", "--regions", "US"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+
+    let stdout = String::from_utf8(out.stdout).unwrap();
+    assert!(stdout.contains("Per month (USD)"), "{}", stdout);
+    let us = stdout.lines().find(|l| l.contains("United States")).unwrap();
+    assert!(us.contains("P1Y") && us.contains("$3.33") && us.contains("pay-as-you-go $0.99 3 × P1M"), "{}", us);
+}
+
+#[test]
+fn group_and_exclude_select_storefronts() {
+    let server = storefront();
+    let out = run(&server, &["--iap", "1", "--group", "na", "--exclude", "CA", "--format", "json"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    let rows: Vec<Value> = serde_json::from_slice(&out.stdout).unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0]["region_code"], "US");
+    assert_eq!(server.hits("/ca/app/id1234567890"), 0);
+    assert_eq!(server.hits("/gb/app/id1234567890"), 0);
+}
+
+#[test]
+fn base_region_comes_from_currency_or_flag() {
+    let server = storefront();
+    server
+        .fixture("/de/app/id1234567890", "iap_app_renamed.html")
+        .route("/fx/EUR", 200, fx_body("EUR", &[("EUR", 1.0), ("USD", 1.1)]));
+    let out = bin()
+        .args(["1234567890", "--currency", "EUR", "--yes", "--all-iaps", "--regions", "US", "--format", "json"])
+        .args(["--store-url", &server.url, "--fx-url", &format!("{}/fx", server.url)])
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    assert!(server.hits("/de/app/id1234567890") > 0);
+    assert_eq!(server.hits("/dz/app/id1234567890"), 0);
+
+    let out = run(&server, &["--base-region", "JP", "--iap", "1", "--regions", "US", "--format", "json"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    assert!(server.hits("/jp/app/id1234567890") > 0);
+}
+
+#[test]
+fn store_url_gives_app_and_base_storefront() {
+    let server = storefront();
+    let out = bin()
+        .arg("https://apps.apple.com/jp/app/idle-ledger/id1234567890?l=en&mt=8")
+        .args(["--currency", "USD", "--yes", "--iap", "1", "--regions", "US", "--format", "json"])
+        .args(["--store-url", &server.url, "--fx-url", &format!("{}/fx", server.url)])
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    // The IAP list came from the link's storefront, in the link's language.
+    assert!(server.hits("/jp/app/id1234567890") > 0);
+    let jp = server.requests().into_iter().find(|r| r.path.starts_with("/jp/")).unwrap();
+    assert_eq!(jp.headers.get("accept-language").map(String::as_str), Some("en"));
+}
+
+#[test]
+fn manual_rates_skip_the_fx_api() {
+    let server = storefront();
+    let out = run(&server, &["--iap", "1", "--regions", "US,JP", "--fx-rate", "JPY=100", "--format", "json"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    let rows: Vec<Value> = serde_json::from_slice(&out.stdout).unwrap();
+    let japan = rows.iter().find(|r| r["region_code"] == "JP").unwrap();
+    assert_eq!(japan["converted_amount"], 5.0);
+    assert_eq!(server.hits("/fx/USD"), 0);
+}
+
+#[test]
+fn unusable_manual_rates_fail() {
+    let server = storefront();
+    for rate in ["JPY=NaN", "JPY=0"] {
+        let out = run(&server, &["--iap", "1", "--regions", "US,JP", "--fx-rate", rate]);
+        assert_eq!(out.status.code(), Some(1));
+        let stderr = String::from_utf8_lossy(&out.stderr);
+        assert!(stderr.contains(&format!("--fx-rate `{}` has an invalid rate", rate)), "{}", stderr);
+    }
+}
+
+#[test]
+fn unknown_iap_selector_fails() {
+    let server = storefront();
+    let out = run(&server, &["--iap", "does-not-exist", "--regions", "US"]);
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("No in-app purchase matches"));
+}
+
+#[tokio::test]
+async fn library_client_uses_configured_endpoints() {
+    let server = MockServer::start();
+    server.fixture("/gb/app/id42", "paid_app_og.html");
+    let client = PricingClient::new().with_store_url(&server.url);
+
+    let regions = [find_region("GB").unwrap(), find_region("FR").unwrap()];
+    let mut results: Vec<_> = client.app_prices("42", &regions).collect().await;
+    results.sort_by_key(|(r, _)| r.code);
+
+    assert!(matches!(&results[0].1, Ok(RegionOutcome::NotAvailable)), "{:?}", results[0]);
+    match &results[1].1 {
+        Ok(RegionOutcome::Found(p)) => assert_eq!((p.amount, p.currency.as_str()), (4.99, "GBP")),
+        other => panic!("expected a price, got {:?}", other),
+    }
+}
+
+#[test]
+fn summary_classifies_failed_regions() {
+    let server = storefront();
+    server
+        .route("/gb/app/id1234567890", 429, "")
+        .fixture("/de/app/id1234567890", "malformed.html");
+    let out = run(&server, &["--iap", "1", "--regions", "US,GB,DE,FR", "--retries", "1", "--format", "json"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(
+        stderr.contains("Priced 1 of 4 regions (1 not available (404), 1 rate limited, 1 failed to parse)"),
+        "{}",
+        stderr
+    );
+    // The throttled storefront was retried once before giving up.
+    assert_eq!(server.hits("/gb/app/id1234567890"), 2);
+}
+
+#[test]
+fn requests_carry_configured_headers() {
+    let server = storefront();
+    let out = run(&server, &["--iap", "1", "--regions", "US", "--user-agent", "pricing-bot/2.0", "--accept-language", "fr-FR"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+
+    let requests = server.requests();
+    assert!(requests.iter().any(|r| r.path == "/fx/USD"));
+    for r in &requests {
+        assert_eq!(r.headers.get("user-agent").map(String::as_str), Some("pricing-bot/2.0"), "{}", r.path);
+        assert_eq!(r.headers.get("accept-language").map(String::as_str), Some("fr-FR"), "{}", r.path);
+        let encodings = r.headers.get("accept-encoding").cloned().unwrap_or_default();
+        assert!(encodings.contains("gzip") && encodings.contains("br"), "{}", encodings);
+    }
+}
+
+#[test]
+fn requests_go_through_proxy() {
+    let proxy = MockServer::start();
+    proxy
+        .fixture("http://apps.example.test/us/app/id1234567890", "iap_app.html")
+        .route("http://fx.example.test/latest/USD", 200, fx_body("USD", &[("USD", 1.0)]));
+    let out = bin()
+        .args(["1234567890", "-c", "USD", "-y", "--iap", "1", "-r", "US", "-f", "json"])
+        .args(["--store-url", "http://apps.example.test", "--fx-url", "http://fx.example.test/latest"])
+        .args(["--proxy", &proxy.url])
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    let rows: Vec<Value> = serde_json::from_slice(&out.stdout).unwrap();
+    assert_eq!(rows[0]["converted_amount"], 2.99);
+    assert!(proxy.hits("http://apps.example.test/us/app/id1234567890") > 0);
+}
+
+#[test]
+fn cached_runs_stay_off_the_network() {
+    let server = storefront();
+    let cache = tempfile::tempdir().unwrap();
+    let cached = |extra: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_appstore_pricing"))
+            .args(["1234567890", "--currency", "USD", "--yes", "--iap", "1", "--regions", "US,JP", "--format", "json"])
+            .args(["--store-url", &server.url, "--fx-url", &format!("{}/fx", server.url)])
+            .arg("--no-history")
+            .arg("--cache-dir")
+            .arg(cache.path())
+            .args(extra)
+            .output()
+            .unwrap()
+    };
+
+    let first = cached(&[]);
+    assert!(first.status.success(), "{}", String::from_utf8_lossy(&first.stderr));
+    let fetched = server.requests().len();
+    assert!(server.hits("/jp/app/id1234567890") > 0);
+
+    let second = cached(&[]);
+    assert!(second.status.success(), "{}", String::from_utf8_lossy(&second.stderr));
+    assert_eq!(server.requests().len(), fetched);
+    let amounts = |out: &Output| {
+        let rows: Vec<Value> = serde_json::from_slice(&out.stdout).unwrap();
+        rows.iter().map(|r| (r["region_code"].clone(), r["converted_amount"].clone())).collect::<Vec<_>>()
+    };
+    assert_eq!(amounts(&first), amounts(&second));
+
+    let offline = cached(&["--offline", "--regions", "US,JP,GB"]);
+    assert!(offline.status.success(), "{}", String::from_utf8_lossy(&offline.stderr));
+    assert_eq!(server.hits("/gb/app/id1234567890"), 0);
+    assert_eq!(server.requests().len(), fetched);
+
+    let refreshed = cached(&["--refresh"]);
+    assert!(refreshed.status.success(), "{}", String::from_utf8_lossy(&refreshed.stderr));
+    assert_eq!(server.hits("/fx/USD"), 2);
+
+    let expired = cached(&["--cache-ttl", "0s"]);
+    assert!(expired.status.success(), "{}", String::from_utf8_lossy(&expired.stderr));
+    assert_eq!(server.hits("/fx/USD"), 3);
+}
+
+#[test]
+fn runs_are_saved_and_can_be_shown_again() {
+    let server = storefront();
+    let dir = tempfile::tempdir().unwrap();
+    let db = dir.path().join("history.sqlite3");
+    let db = db.to_str().unwrap();
+    let priced = |args: &[&str]| {
+        let out = Command::new(env!("CARGO_BIN_EXE_appstore_pricing"))
+            .args(["1234567890", "--currency", "USD", "--yes", "--no-cache", "--history-db", db])
+            .args(["--store-url", &server.url, "--fx-url", &format!("{}/fx", server.url)])
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    };
+    priced(&["--iap", "com.ledgerlabs.pocketledger.removeads", "--regions", "US,JP"]);
+    priced(&["--all-iaps", "--regions", "US"]);
+
+    let query = |args: &[&str]| {
+        let out = Command::new(env!("CARGO_BIN_EXE_appstore_pricing")).args(args).args(["--history-db", db]).output().unwrap();
+        assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+        serde_json::from_slice::<Value>(&out.stdout).unwrap()
+    };
+    let snapshots = query(&["history", "https://apps.apple.com/us/app/id1234567890", "--format", "json"]);
+    assert_eq!(snapshots.as_array().unwrap().len(), 2);
+    assert_eq!(snapshots[0]["id"], 2);
+    assert_eq!(snapshots[1]["regions"], 2);
+    assert_eq!(snapshots[1]["iap_keys"], serde_json::json!(["com.ledgerlabs.pocketledger.removeads"]));
+
+    let only = query(&["history", "--iap", "com.ledgerlabs.pocketledger.removeads", "--format", "json"]);
+    assert_eq!(only.as_array().unwrap().len(), 2);
+
+    let first = query(&["show", "1", "--format", "json"]);
+    let japan = first.as_array().unwrap().iter().find(|r| r["region_code"] == "JP").unwrap();
+    assert_eq!((japan["amount"].as_f64(), japan["currency"].as_str()), (Some(500.0), Some("JPY")));
+    assert_eq!(japan["product_id"], "com.ledgerlabs.pocketledger.removeads");
+
+    let latest = query(&["show", "--app", "1234567890", "--iap", "com.ledgerlabs.pocketledger.removeads", "--format", "json"]);
+    assert_eq!(latest.as_array().unwrap().len(), 1);
+    assert_eq!(latest[0]["region_code"], "US");
+
+    let missing = Command::new(env!("CARGO_BIN_EXE_appstore_pricing")).args(["show", "9", "--history-db", db]).output().unwrap();
+    assert!(!missing.status.success());
+}
+
+#[test]
+fn diff_reports_changes_with_an_exit_code() {
+    let server = storefront();
+    let dir = tempfile::tempdir().unwrap();
+    let db = dir.path().join("history.sqlite3");
+    let db = db.to_str().unwrap();
+    let fetch = ["--store-url", &server.url, "--fx-url", &format!("{}/fx", server.url), "--no-cache", "--history-db", db];
+    let cli = |args: &[&str]| Command::new(env!("CARGO_BIN_EXE_appstore_pricing")).args(fetch).args(args).output().unwrap();
+
+    let out = cli(&["1234567890", "--currency", "USD", "--yes", "--iap", "1", "--regions", "US,JP,GB"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+
+    let unchanged = cli(&["diff", "--app", "1234567890"]);
+    assert_eq!(unchanged.status.code(), Some(0), "{}", String::from_utf8_lossy(&unchanged.stderr));
+
+    let jp = std::fs::read_to_string(format!("{}/tests/fixtures/iap_app_jp.html", env!("CARGO_MANIFEST_DIR"))).unwrap();
+    server.route(
+        "/jp/app/id1234567890",
+        200,
+        jp.replace(r#"\"price\": 500, \"currencyCode\": \"JPY\", \"priceFormatted\": \"¥500\""#, r#"\"price\": 600, \"currencyCode\": \"JPY\", \"priceFormatted\": \"¥600\""#),
+    );
+    let changed = cli(&["diff", "1", "--format", "json"]);
+    assert_eq!(changed.status.code(), Some(1), "{}", String::from_utf8_lossy(&changed.stderr));
+    let changes: Vec<Value> = serde_json::from_slice(&changed.stdout).unwrap();
+    assert_eq!(changes.len(), 1);
+    assert_eq!((changes[0]["region_code"].as_str(), changes[0]["change"].as_str()), (Some("JP"), Some("changed")));
+    assert_eq!((changes[0]["old_amount"].as_f64(), changes[0]["new_amount"].as_f64()), (Some(500.0), Some(600.0)));
+    assert_eq!(changes[0]["amount_change_pct"].as_f64(), Some(20.0));
+
+    // Both "now" comparisons were saved, so snapshots can be compared directly.
+    let saved = cli(&["diff", "2", "3", "--format", "csv"]);
+    assert_eq!(saved.status.code(), Some(1), "{}", String::from_utf8_lossy(&saved.stderr));
+    let csv = String::from_utf8_lossy(&saved.stdout);
+    assert!(csv.starts_with("region_code,region_name,product_id,change,"));
+    assert!(csv.contains("\nJP,Japan,com.ledgerlabs.pocketledger.removeads,changed,500,JPY,600,JPY,20,"));
+
+    assert_eq!(cli(&["diff", "42"]).status.code(), Some(2));
+}
+
+#[test]
+fn diff_against_now_reports_a_removed_iap() {
+    let server = storefront();
+    server.fixture("/gb/app/id1234567890", "iap_app.html");
+    let dir = tempfile::tempdir().unwrap();
+    let db = dir.path().join("history.sqlite3");
+    let db = db.to_str().unwrap();
+    let fetch = ["--store-url", &server.url, "--fx-url", &format!("{}/fx", server.url), "--no-cache", "--history-db", db];
+    let cli = |args: &[&str]| Command::new(env!("CARGO_BIN_EXE_appstore_pricing")).args(fetch).args(args).output().unwrap();
+
+    let out = cli(&["1234567890", "--currency", "USD", "--yes", "--iap", "com.ledgerlabs.pocketledger.pro.monthly", "--regions", "US,GB"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+
+    server.route("/gb/app/id1234567890", 200, without_pro_monthly());
+    let changed = cli(&["diff", "--app", "1234567890", "--format", "json"]);
+    assert_eq!(changed.status.code(), Some(1), "{}", String::from_utf8_lossy(&changed.stderr));
+    let changes: Vec<Value> = serde_json::from_slice(&changed.stdout).unwrap();
+    assert_eq!(changes.len(), 1);
+    assert_eq!((changes[0]["region_code"].as_str(), changes[0]["change"].as_str()), (Some("GB"), Some("removed")));
+}
+
+#[test]
+fn diff_against_now_reads_the_iap_list_from_a_snapshot_region() {
+    // Sold in Japan only: the US storefront answers 404.
+    let server = MockServer::start();
+    server
+        .fixture("/jp/app/id1234567890", "iap_app_jp.html")
+        .route("/fx/USD", 200, fx_body("USD", &[("USD", 1.0), ("JPY", 150.0)]));
+    let dir = tempfile::tempdir().unwrap();
+    let db = dir.path().join("history.sqlite3");
+    let db = db.to_str().unwrap();
+    let fetch = ["--store-url", &server.url, "--fx-url", &format!("{}/fx", server.url), "--no-cache", "--history-db", db];
+    let cli = |args: &[&str]| Command::new(env!("CARGO_BIN_EXE_appstore_pricing")).args(fetch).args(args).output().unwrap();
+
+    let out = cli(&["1234567890", "--currency", "USD", "--yes", "--base-region", "JP", "--regions", "JP", "--iap", "com.ledgerlabs.pocketledger.removeads"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+
+    let unchanged = cli(&["diff", "--app", "1234567890"]);
+    assert_eq!(unchanged.status.code(), Some(0), "{}", String::from_utf8_lossy(&unchanged.stderr));
+}
+
+#[test]
+fn watch_emits_only_changed_prices() {
+    let server = storefront();
+    server.route("/hook", 200, "");
+    let dir = tempfile::tempdir().unwrap();
+    let db = dir.path().join("history.sqlite3");
+    let db = db.to_str().unwrap();
+    let fetch = ["--store-url", &server.url, "--fx-url", &format!("{}/fx", server.url), "--no-cache", "--history-db", db];
+    let watch = |args: &[&str]| {
+        let out = Command::new(env!("CARGO_BIN_EXE_appstore_pricing"))
+            .args(["watch", "1234567890@com.ledgerlabs.pocketledger.removeads", "--once", "--regions", "US,JP"])
+            .args(fetch)
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+        String::from_utf8(out.stdout).unwrap()
+    };
+
+    assert_eq!(watch(&[]), "");
+    assert_eq!(watch(&[]), "");
+
+    let jp = std::fs::read_to_string(format!("{}/tests/fixtures/iap_app_jp.html", env!("CARGO_MANIFEST_DIR"))).unwrap();
+    server.route(
+        "/jp/app/id1234567890",
+        200,
+        jp.replace(r#"\"price\": 500, \"currencyCode\": \"JPY\", \"priceFormatted\": \"¥500\""#, r#"\"price\": 600, \"currencyCode\": \"JPY\", \"priceFormatted\": \"¥600\""#),
+    );
+    let out = watch(&[]);
+    let events: Vec<Value> = out.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+    assert_eq!(events.len(), 1);
+    assert_eq!((events[0]["app_id"].as_str(), events[0]["region_code"].as_str()), (Some("1234567890"), Some("JP")));
+    assert_eq!((events[0]["old_amount"].as_f64(), events[0]["new_amount"].as_f64()), (Some(500.0), Some(600.0)));
+
+    server.route(
+        "/jp/app/id1234567890",
+        200,
+        jp.replace(r#"\"price\": 500, \"currencyCode\": \"JPY\", \"priceFormatted\": \"¥500\""#, r#"\"price\": 700, \"currencyCode\": \"JPY\", \"priceFormatted\": \"¥700\""#),
+    );
+    assert_eq!(watch(&["--webhook", &format!("{}/hook", server.url)]), "");
+    let posted = server.posted("/hook");
+    assert_eq!(posted.len(), 1);
+    let payload: Value = serde_json::from_str(&posted[0]).unwrap();
+    assert_eq!(payload["events"][0]["new_amount"], 700.0);
+}
+
+#[test]
+fn batch_prices_every_app_with_one_rate_table() {
+    let server = storefront();
+    server.fixture("/us/app/id42", "paid_app_og.html").fixture("/gb/app/id42", "paid_app_og.html");
+    let dir = tempfile::tempdir().unwrap();
+    let list = dir.path().join("apps.txt");
+    std::fs::write(
+        &list,
+        "# ours\n1234567890@com.ledgerlabs.pocketledger.removeads\n\n# theirs\n42 GB\n404404404\n",
+    )
+    .unwrap();
+
+    let out = bin()
+        .args(["batch", list.to_str().unwrap(), "--format", "csv", "--regions", "US,JP"])
+        .args(["--store-url", &server.url, "--fx-url", &format!("{}/fx", server.url)])
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert_eq!(out.status.code(), Some(1), "{}", stderr);
+    assert!(stderr.contains("1 of 3 apps couldn't be priced"), "{}", stderr);
+
+    let csv = String::from_utf8(out.stdout).unwrap();
+    let rows: Vec<Vec<&str>> = csv.lines().map(|l| l.split(',').collect()).collect();
+    assert_eq!(rows[0][..4], ["app_id", "app_name", "region_code", "region_name"]);
+    let summary: Vec<_> = rows[1..].iter().map(|r| (r[0], r[2], r[4], r[6])).collect();
+    assert_eq!(
+        summary,
+        [("1234567890", "US", "2.99", "2.99"), ("1234567890", "JP", "500", "3.33"), ("42", "GB", "4.99", "6.24")]
+    );
+    assert_eq!(server.hits("/fx/USD"), 1);
+}
+
+#[test]
+fn batch_iap_selector_is_not_matched_to_another_iap() {
+    let server = storefront();
+    let dir = tempfile::tempdir().unwrap();
+    let list = dir.path().join("apps.txt");
+    std::fs::write(&list, "1234567890@com.ledgerlabs.pocketledger.pro.monthly US,GB\n").unwrap();
+    server.route("/gb/app/id1234567890", 200, without_pro_monthly());
+
+    let out = bin()
+        .args(["batch", list.to_str().unwrap(), "--format", "json", "--currency", "USD"])
+        .args(["--store-url", &server.url, "--fx-url", &format!("{}/fx", server.url)])
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    let rows: Vec<Value> = serde_json::from_slice(&out.stdout).unwrap();
+    let summary: Vec<_> = rows.iter().map(|r| (r["region_code"].as_str(), r["product_id"].as_str(), r["amount"].as_f64())).collect();
+    assert_eq!(summary, [(Some("US"), Some("com.ledgerlabs.pocketledger.pro.monthly"), Some(4.99))]);
+}
+
+/// `watch --once` against `server` with its history in `db`, returning
+/// stdout and stderr.
+fn watch_once(server: &MockServer, db: &str, args: &[&str]) -> (String, String) {
+    let out = Command::new(env!("CARGO_BIN_EXE_appstore_pricing"))
+        .args(["watch", "--once", "--no-cache", "--history-db", db])
+        .args(["--store-url", &server.url, "--fx-url", &format!("{}/fx", server.url)])
+        .args(args)
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8(out.stderr).unwrap();
+    assert!(out.status.success(), "{}", stderr);
+    (String::from_utf8(out.stdout).unwrap(), stderr)
+}
+
+#[test]
+fn watch_compares_only_what_the_saved_snapshot_priced() {
+    let server = storefront();
+    let dir = tempfile::tempdir().unwrap();
+    let db = dir.path().join("history.sqlite3");
+    let db = db.to_str().unwrap();
+    let saved = Command::new(env!("CARGO_BIN_EXE_appstore_pricing"))
+        .args(["1234567890", "--currency", "USD", "--yes", "--no-cache", "--history-db", db])
+        .args(["--store-url", &server.url, "--fx-url", &format!("{}/fx", server.url)])
+        .args(["--iap", "com.ledgerlabs.pocketledger.removeads", "--regions", "US"])
+        .output()
+        .unwrap();
+    assert!(saved.status.success(), "{}", String::from_utf8_lossy(&saved.stderr));
+
+    // Every IAP in two regions, against a snapshot of one IAP in one region.
+    let (out, stderr) = watch_once(&server, db, &["1234567890", "--regions", "US,JP"]);
+    assert_eq!(out, "", "{}", stderr);
+    assert!(stderr.contains("0 price changes"), "{}", stderr);
+}
+
+#[test]
+fn watch_skips_a_snapshot_in_another_base_currency() {
+    let server = storefront();
+    // EUR runs read the IAP list from the German storefront.
+    server
+        .fixture("/de/app/id1234567890", "iap_app.html")
+        .route("/fx/EUR", 200, fx_body("EUR", &[("EUR", 1.0), ("USD", 1.1), ("JPY", 160.0)]));
+    let dir = tempfile::tempdir().unwrap();
+    let db = dir.path().join("history.sqlite3");
+    let db = db.to_str().unwrap();
+    let target = "1234567890@com.ledgerlabs.pocketledger.removeads";
+    let (_, stderr) = watch_once(&server, db, &[target, "--regions", "US,JP", "--currency", "EUR"]);
+    assert!(stderr.contains("Saved as snapshot #1"), "{}", stderr);
+
+    let jp = std::fs::read_to_string(format!("{}/tests/fixtures/iap_app_jp.html", env!("CARGO_MANIFEST_DIR"))).unwrap();
+    server.route(
+        "/jp/app/id1234567890",
+        200,
+        jp.replace(r#"\"price\": 500, \"currencyCode\": \"JPY\", \"priceFormatted\": \"¥500\""#, r#"\"price\": 600, \"currencyCode\": \"JPY\", \"priceFormatted\": \"¥600\""#),
+    );
+    let (out, stderr) = watch_once(&server, db, &[target, "--regions", "US,JP", "--currency", "USD"]);
+    assert_eq!(out, "", "{}", stderr);
+    assert!(stderr.contains("was converted to EUR, not USD"), "{}", stderr);
+
+    // The USD poll that was just saved is a fair baseline for the next one.
+    let (out, _) = watch_once(&server, db, &[target, "--regions", "US,JP", "--currency", "USD"]);
+    assert_eq!(out, "");
+}
+
+#[test]
+fn watched_iap_missing_from_a_storefront_is_removed() {
+    let server = storefront();
+    server.fixture("/gb/app/id1234567890", "iap_app.html");
+    let dir = tempfile::tempdir().unwrap();
+    let db = dir.path().join("history.sqlite3");
+    let db = db.to_str().unwrap();
+    let target = "1234567890@com.ledgerlabs.pocketledger.pro.monthly";
+    let (_, stderr) = watch_once(&server, db, &[target, "--regions", "US,GB", "--currency", "USD"]);
+    assert!(stderr.contains("Saved as snapshot #1"), "{}", stderr);
+
+    server.route("/gb/app/id1234567890", 200, without_pro_monthly());
+    let (out, stderr) = watch_once(&server, db, &[target, "--regions", "US,GB", "--currency", "USD"]);
+    let events: Vec<Value> = out.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+    assert_eq!(events.len(), 1, "{}", stderr);
+    assert_eq!((events[0]["region_code"].as_str(), events[0]["change"].as_str()), (Some("GB"), Some("removed")));
+    assert_eq!(events[0]["product_id"], "com.ledgerlabs.pocketledger.pro.monthly");
+    assert_eq!((events[0]["old_amount"].as_f64(), events[0]["new_amount"].as_f64()), (Some(4.99), None));
+}
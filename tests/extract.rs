@@ -0,0 +1,237 @@
+//! Extraction against saved storefront pages; no network access.
+
+use appstore_pricing::extract::{self, Page};
+use appstore_pricing::{find_region, InAppPurchase, Region, RegionError, RegionOutcome};
+
+fn fixture(name: &str) -> Page {
+    let path = format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name);
+    Page::new(200, std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("{}: {}", path, e)))
+}
+
+fn region(code: &str) -> Region {
+    find_region(code).unwrap()
+}
+
+fn found(outcome: Result<RegionOutcome, RegionError>) -> (f64, String) {
+    match outcome {
+        Ok(RegionOutcome::Found(p)) => (p.amount, p.currency),
+        other => panic!("expected a price, got {:?}", other),
+    }
+}
+
+fn us_iaps() -> Vec<InAppPurchase> {
+    extract::in_app_purchases(&fixture("iap_app.html")).unwrap().unwrap()
+}
+
+fn remove_ads() -> InAppPurchase {
+    us_iaps().into_iter().find(|i| i.name == "Remove Ads").unwrap()
+}
+
+#[test]
+fn free_app_price_comes_from_shoebox() {
+    let outcome = extract::app_outcome(&fixture("free_app.html"), &region("US"));
+    assert_eq!(found(outcome), (0.0, "USD".to_string()));
+}
+
+#[test]
+fn paid_app_price_from_open_graph() {
+    let outcome = extract::app_outcome(&fixture("paid_app_og.html"), &region("GB"));
+    assert_eq!(found(outcome), (4.99, "GBP".to_string()));
+}
+
+#[test]
+fn paid_app_price_from_json_ld() {
+    let outcome = extract::app_outcome(&fixture("paid_app_jsonld.html"), &region("DE"));
+    assert_eq!(found(outcome), (2.99, "EUR".to_string()));
+}
+
+#[test]
+fn legacy_price_list_item_yields_label() {
+    match extract::app_outcome(&fixture("paid_app_legacy_li.html"), &region("FR")) {
+        Ok(RegionOutcome::Label(label)) => assert_eq!(label, "4,99 €"),
+        other => panic!("expected a label, got {:?}", other),
+    }
+}
+
+#[test]
+fn found_price_carries_region() {
+    match extract::app_outcome(&fixture("paid_app_og.html"), &region("GB")) {
+        Ok(RegionOutcome::Found(p)) => {
+            assert_eq!(p.region_code, "GB");
+            assert_eq!(p.region_name, "United Kingdom");
+            assert!(p.converted_amount.is_none());
+        }
+        other => panic!("expected a price, got {:?}", other),
+    }
+}
+
+#[test]
+fn app_name_from_open_graph() {
+    assert_eq!(extract::app_name(&fixture("iap_app.html")).as_deref(), Some("Pocket Ledger"));
+    assert_eq!(extract::app_name(&fixture("iap_app_jp.html")).as_deref(), Some("ポケット家計簿"));
+}
+
+#[test]
+fn lists_top_in_app_purchases() {
+    let iaps = extract::in_app_purchases(&fixture("iap_app.html")).unwrap().unwrap();
+    let names: Vec<_> = iaps.iter().map(|i| i.name.as_str()).collect();
+    assert_eq!(names, ["Remove Ads", "Pro Monthly", "Coin Pack — 500"]);
+    assert_eq!(iaps[1].id, "1500000002");
+    assert_eq!(iaps[1].offer_name, "com.ledgerlabs.pocketledger.pro.monthly");
+    assert_eq!(iaps[1].price_formatted, "$4.99");
+}
+
+#[test]
+fn app_without_iaps_lists_none() {
+    let iaps = extract::in_app_purchases(&fixture("free_app.html")).unwrap().unwrap();
+    assert!(iaps.is_empty());
+}
+
+#[test]
+fn iap_price_matched_across_localized_storefront() {
+    let outcome = extract::iap_outcome(&fixture("iap_app_jp.html"), &region("JP"), &remove_ads(), &us_iaps());
+    assert_eq!(found(outcome), (500.0, "JPY".to_string()));
+}
+
+#[test]
+fn iap_missing_from_storefront_has_no_price() {
+    let coins = extract::in_app_purchases(&fixture("iap_app.html")).unwrap().unwrap().remove(2);
+    let outcome = extract::iap_outcome(&fixture("iap_app_jp.html"), &region("JP"), &coins, &us_iaps());
+    assert!(matches!(outcome, Ok(RegionOutcome::NoPrice)), "{:?}", outcome);
+}
+
+#[test]
+fn missing_iap_is_not_matched_to_another_known_iap() {
+    // The storefront lists Remove Ads and the coin pack, but not Pro Monthly.
+    let body = fixture("iap_app.html").body;
+    let (start, end) = (body.find(r#"{\"id\": \"1500000002\""#).unwrap(), body.find(r#"{\"id\": \"1500000003\""#).unwrap());
+    let page = Page::new(200, format!("{}{}", &body[..start], &body[end..]));
+    let pro = us_iaps().into_iter().find(|i| i.name == "Pro Monthly").unwrap();
+    let outcome = extract::iap_outcome(&page, &region("US"), &pro, &us_iaps());
+    assert!(matches!(outcome, Ok(RegionOutcome::NoPrice)), "{:?}", outcome);
+    assert_eq!(found(extract::iap_outcome(&page, &region("US"), &remove_ads(), &us_iaps())), (2.99, "USD".to_string()));
+}
+
+#[test]
+fn iap_offer_without_price_is_a_parse_error() {
+    let outcome = extract::iap_outcome(&fixture("iap_missing_price.html"), &region("US"), &remove_ads(), &us_iaps());
+    assert!(matches!(outcome, Err(RegionError::Parse(_))), "{:?}", outcome);
+}
+
+#[test]
+fn all_iap_prices_from_one_page() {
+    let prices = extract::all_iap_prices(&fixture("iap_app_jp.html"), &region("JP"), &us_iaps()).unwrap().unwrap();
+    let cells: Vec<_> = prices.iter().map(|p| (p.product_id.as_deref().unwrap(), p.amount, p.currency.as_str())).collect();
+    assert_eq!(
+        cells,
+        [
+            ("com.ledgerlabs.pocketledger.removeads", 500.0, "JPY"),
+            ("com.ledgerlabs.pocketledger.pro.monthly", 800.0, "JPY"),
+        ]
+    );
+}
+
+#[test]
+fn iaps_are_ranked_by_price() {
+    let tiers: Vec<_> = us_iaps().iter().map(|i| i.price_tier).collect();
+    assert_eq!(tiers, [Some(0), Some(1), Some(2)]);
+}
+
+#[test]
+fn renamed_iaps_are_matched_by_id_then_name_then_price_tier() {
+    let page = fixture("iap_app_renamed.html");
+    let matched: Vec<_> = us_iaps()
+        .iter()
+        .map(|iap| match extract::iap_outcome(&page, &region("DE"), iap, &us_iaps()) {
+            Ok(RegionOutcome::Found(p)) => (p.amount, p.product_id.unwrap(), p.match_note),
+            other => panic!("expected a price for {}, got {:?}", iap.name, other),
+        })
+        .collect();
+    assert_eq!(
+        matched,
+        [
+            (2.99, "com.ledgerlabs.pocketledger.removeads".to_string(), None),
+            (
+                5.49,
+                "com.ledgerlabs.pocketledger.pro.monthly".to_string(),
+                Some("matched by name similarity to \"Pro-Monthly\"".to_string())
+            ),
+            (
+                10.99,
+                "com.ledgerlabs.pocketledger.coins500".to_string(),
+                Some("matched by price tier #3 (\"Münzpaket — 500\")".to_string())
+            ),
+        ]
+    );
+}
+
+#[test]
+fn all_iap_prices_line_up_renamed_iaps() {
+    let prices = extract::all_iap_prices(&fixture("iap_app_renamed.html"), &region("DE"), &us_iaps()).unwrap().unwrap();
+    let ids: Vec<_> = prices.iter().map(|p| p.product_id.as_deref().unwrap()).collect();
+    assert_eq!(
+        ids,
+        [
+            "com.ledgerlabs.pocketledger.removeads",
+            "com.ledgerlabs.pocketledger.pro.monthly",
+            "com.ledgerlabs.pocketledger.coins500",
+        ]
+    );
+}
+
+#[test]
+fn shortlist_with_next_link_is_incomplete() {
+    let mut listing = extract::iap_listing(&fixture("iap_app_paged.html")).unwrap().unwrap();
+    assert!(!listing.is_complete());
+    assert_eq!(listing.next.as_deref(), Some("/v1/catalog/us/apps/1234567890/top-in-apps?offset=3"));
+    assert_eq!(listing.token.as_deref(), Some("fixture-token"));
+
+    let page2 = fixture("iap_catalog_page2.json").body;
+    listing.extend_from_page(&page2).unwrap();
+    assert!(listing.is_complete());
+    let names: Vec<_> = listing.purchases().into_iter().map(|p| (p.name, p.price_tier)).collect();
+    assert_eq!(
+        names,
+        [
+            ("Remove Ads".to_string(), Some(1)),
+            ("Pro Monthly".to_string(), Some(2)),
+            ("Coin Pack — 500".to_string(), Some(3)),
+            ("Theme Pack".to_string(), Some(0)),
+            ("Pro Yearly".to_string(), Some(4)),
+        ]
+    );
+}
+
+#[test]
+fn prices_are_flagged_with_listing_completeness() {
+    let prices = extract::all_iap_prices(&fixture("iap_app_paged.html"), &region("US"), &[]).unwrap().unwrap();
+    assert!(prices.iter().all(|p| p.catalog_complete == Some(false)));
+    let prices = extract::all_iap_prices(&fixture("iap_app.html"), &region("US"), &[]).unwrap().unwrap();
+    assert!(prices.iter().all(|p| p.catalog_complete == Some(true)));
+}
+
+#[test]
+fn unavailable_storefront() {
+    let page = Page::new(404, fixture("unavailable.html").body);
+    assert!(matches!(extract::app_outcome(&page, &region("CN")), Ok(RegionOutcome::NotAvailable)));
+    assert!(matches!(
+        extract::iap_outcome(&page, &region("CN"), &remove_ads(), &us_iaps()),
+        Ok(RegionOutcome::NotAvailable)
+    ));
+    assert!(extract::in_app_purchases(&page).unwrap().is_none());
+    assert!(extract::all_iap_prices(&page, &region("CN"), &us_iaps()).unwrap().is_none());
+    assert!(extract::app_name(&page).is_none());
+}
+
+#[test]
+fn malformed_shoebox_is_a_parse_error() {
+    let page = fixture("malformed.html");
+    assert!(matches!(extract::in_app_purchases(&page), Err(RegionError::Parse(_))));
+    assert!(matches!(extract::app_outcome(&page, &region("US")), Err(RegionError::Parse(_))));
+}
+
+#[test]
+fn page_without_any_price_data_is_a_parse_error() {
+    let page = fixture("unavailable.html");
+    assert!(matches!(extract::app_outcome(&page, &region("US")), Err(RegionError::Parse(_))));
+}
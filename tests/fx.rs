@@ -0,0 +1,96 @@
+//! Exchange-rate providers against canned feeds and files.
+
+mod common;
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use appstore_pricing::fx::{parse_rates_file, rebase, Ecb, FxProvider, ManualRates, OpenErApi, RatesFile};
+use appstore_pricing::{convert_prices, find_region, Pricing};
+use appstore_pricing::transport::ReqwestTransport;
+use common::{fx_body, MockServer};
+
+fn fixture_path(name: &str) -> PathBuf {
+    PathBuf::from(format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name))
+}
+
+fn approx(rates: &HashMap<String, f64>, code: &str) -> f64 {
+    (rates[code] * 10_000.0).round() / 10_000.0
+}
+
+#[test]
+fn rebasing() {
+    let eur = HashMap::from([("USD".to_string(), 1.25), ("JPY".to_string(), 150.0)]);
+    let usd = rebase(&eur, "EUR", "USD").unwrap();
+    assert_eq!((usd["USD"], usd["EUR"], usd["JPY"]), (1.0, 0.8, 120.0));
+    assert!(rebase(&eur, "EUR", "GBP").is_err());
+}
+
+#[tokio::test]
+async fn open_er_api() {
+    let server = MockServer::start();
+    server.route("/latest/USD", 200, fx_body("USD", &[("USD", 1.0), ("JPY", 150.0)]));
+    let provider = OpenErApi { url: format!("{}/latest", server.url) };
+    let rates = provider.rates(&ReqwestTransport::default(), "USD").await.unwrap();
+    assert_eq!(rates["JPY"], 150.0);
+}
+
+#[tokio::test]
+async fn ecb_reference_rates_are_rebased() {
+    let server = MockServer::start();
+    server.fixture("/eurofxref-daily.xml", "ecb_daily.xml");
+    let provider = Ecb { url: format!("{}/eurofxref-daily.xml", server.url) };
+    let transport = ReqwestTransport::default();
+
+    let eur = provider.rates(&transport, "EUR").await.unwrap();
+    assert_eq!((eur["EUR"], eur["USD"], eur["JPY"]), (1.0, 1.165, 174.8));
+
+    let usd = provider.rates(&transport, "USD").await.unwrap();
+    assert_eq!(usd["USD"], 1.0);
+    assert_eq!(approx(&usd, "EUR"), 0.8584);
+    assert_eq!(approx(&usd, "JPY"), 150.0429);
+}
+
+#[tokio::test]
+async fn rates_files_in_json_and_csv() {
+    let transport = ReqwestTransport::default();
+    for name in ["rates.json", "rates.csv"] {
+        let provider = RatesFile { path: fixture_path(name) };
+        let usd = provider.rates(&transport, "USD").await.unwrap();
+        assert_eq!(approx(&usd, "EUR"), 0.9091, "{}", name);
+        assert_eq!(approx(&usd, "JPY"), 150.0, "{}", name);
+    }
+    let missing = RatesFile { path: fixture_path("no-such-rates.csv") };
+    assert!(missing.rates(&transport, "USD").await.is_err());
+}
+
+#[tokio::test]
+async fn manual_table() {
+    let provider = ManualRates { base: "USD".into(), rates: HashMap::from([("JPY".to_string(), 100.0)]) };
+    let jpy = provider.rates(&ReqwestTransport::default(), "JPY").await.unwrap();
+    assert_eq!((jpy["JPY"], jpy["USD"]), (1.0, 0.01));
+}
+
+#[tokio::test]
+async fn unusable_rates_are_rejected() {
+    for rate in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+        let provider = ManualRates { base: "USD".into(), rates: HashMap::from([("JPY".to_string(), rate)]) };
+        let err = provider.rates(&ReqwestTransport::default(), "USD").await.unwrap_err();
+        assert!(err.to_string().contains("Rate for JPY must be a positive number"), "{}", err);
+    }
+    assert!(parse_rates_file("base,currency,rate\nUSD,JPY,NaN\n").is_err());
+    assert!(parse_rates_file("base,currency,rate\nUSD,JPY,0\n").is_err());
+    assert!(parse_rates_file(r#"{"base": "USD", "rates": {"JPY": -150}}"#).is_err());
+}
+
+#[test]
+fn conversion_skips_unusable_rates_without_panicking() {
+    let mut pricing = vec![
+        Pricing::new(&find_region("JP").unwrap(), 500.0, "JPY"),
+        Pricing::new(&find_region("GB").unwrap(), 4.49, "GBP"),
+        Pricing::new(&find_region("US").unwrap(), 2.99, "USD"),
+    ];
+    let rates = HashMap::from([("USD".to_string(), 1.0), ("JPY".to_string(), f64::NAN), ("GBP".to_string(), 0.0)]);
+    convert_prices(&mut pricing, "USD", &rates);
+    let converted: Vec<_> = pricing.iter().map(|p| (p.region_code.as_str(), p.converted_amount)).collect();
+    assert_eq!(converted, [("JP", None), ("GB", None), ("US", Some(2.99))]);
+}
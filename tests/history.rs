@@ -0,0 +1,57 @@
+use appstore_pricing::history::History;
+use appstore_pricing::{find_region, Pricing};
+
+fn price(code: &str, amount: f64, currency: &str, product: Option<&str>, converted: Option<f64>) -> Pricing {
+    let mut p = Pricing::new(&find_region(code).unwrap(), amount, currency);
+    p.product_id = product.map(str::to_string);
+    p.converted_amount = converted;
+    p.rate = converted.map(|c| amount / c);
+    p.base_currency = converted.map(|_| "USD".to_string());
+    p
+}
+
+#[test]
+fn snapshots_round_trip() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut history = History::open(&dir.path().join("nested").join("history.sqlite3")).unwrap();
+    let first = history
+        .record("111", Some("Ledger"), "USD", &[
+            price("US", 4.99, "USD", Some("pro"), Some(4.99)),
+            price("JP", 800.0, "JPY", Some("pro"), Some(5.33)),
+            price("JP", 300.0, "JPY", Some("coins"), None),
+        ])
+        .unwrap();
+    let second = history.record("111", Some("Ledger"), "USD", &[price("GB", 4.49, "GBP", Some("coins"), Some(5.61))]).unwrap();
+    let other = history.record("222", None, "EUR", &[price("DE", 2.99, "EUR", None, Some(2.99))]).unwrap();
+    assert!(first < second && second < other);
+
+    let all = history.snapshots(None, None, 10).unwrap();
+    assert_eq!(all.iter().map(|s| s.id).collect::<Vec<_>>(), [other, second, first]);
+    assert_eq!(all[2].iap_keys, ["pro", "coins"]);
+    assert_eq!(all[2].regions, 2);
+    assert!(all[0].iap_keys.is_empty());
+
+    let pro = history.snapshots(Some("111"), Some("pro"), 10).unwrap();
+    assert_eq!(pro.iter().map(|s| s.id).collect::<Vec<_>>(), [first]);
+    assert_eq!(history.latest("111", None).unwrap().unwrap().id, second);
+    assert_eq!(history.snapshots(None, None, 1).unwrap().len(), 1);
+    assert!(history.snapshot(999).unwrap().is_none());
+
+    let prices = history.prices(first, Some("pro")).unwrap();
+    assert_eq!(prices.len(), 2);
+    assert_eq!((prices[1].region_code.as_str(), prices[1].region_name.as_str()), ("JP", "Japan"));
+    assert_eq!((prices[1].amount, prices[1].converted_amount), (800.0, Some(5.33)));
+    assert_eq!(prices[1].base_currency.as_deref(), Some("USD"));
+    let coins = history.prices(first, Some("coins")).unwrap();
+    assert_eq!((coins[0].converted_amount, coins[0].base_currency.as_deref()), (None, None));
+    assert_eq!(history.prices(other, None).unwrap()[0].product_id, None);
+}
+
+#[test]
+fn reopening_keeps_snapshots() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("history.sqlite3");
+    let id = History::open(&path).unwrap().record("111", None, "USD", &[price("US", 0.99, "USD", None, Some(0.99))]).unwrap();
+    let history = History::open(&path).unwrap();
+    assert_eq!(history.snapshot(id).unwrap().unwrap().app_id, "111");
+}
@@ -0,0 +1,109 @@
+//! App Store links in every shape Apple hands out.
+
+use appstore_pricing::link::{parse_app_link, AppLink, ParsedLink};
+use appstore_pricing::transport::{Response, Transport, TransportError};
+use appstore_pricing::PricingClient;
+use async_trait::async_trait;
+
+fn app(input: &str) -> AppLink {
+    match parse_app_link(input) {
+        Ok(ParsedLink::App(link)) => link,
+        other => panic!("{}: expected an app link, got {:?}", input, other),
+    }
+}
+
+fn id(input: &str) -> String {
+    app(input).app_id
+}
+
+#[test]
+fn bare_ids() {
+    assert_eq!(id("284882215"), "284882215");
+    assert_eq!(id("id284882215"), "284882215");
+    assert_eq!(id("  284882215\n"), "284882215");
+}
+
+#[test]
+fn store_urls() {
+    assert_eq!(id("https://apps.apple.com/us/app/facebook/id284882215"), "284882215");
+    assert_eq!(id("https://apps.apple.com/app/id284882215"), "284882215");
+    assert_eq!(id("apps.apple.com/us/app/facebook/id284882215"), "284882215");
+    assert_eq!(id("https://itunes.apple.com/us/app/facebook/id284882215?mt=8"), "284882215");
+    assert_eq!(id("https://apps.apple.com/us/app/facebook/id284882215/"), "284882215");
+    assert_eq!(id("https://apps.apple.com/us/app/facebook/id284882215#see-all/reviews"), "284882215");
+    assert_eq!(id("https://itunes.apple.com/lookup?id=284882215"), "284882215");
+}
+
+#[test]
+fn slugs_containing_id_are_ignored() {
+    assert_eq!(id("https://apps.apple.com/us/app/idle-miner-tycoon/id1116645064"), "1116645064");
+    assert_eq!(id("https://apps.apple.com/us/app/id-scanner-123/id987654321"), "987654321");
+    assert_eq!(id("https://apps.apple.com/us/app/idid/id42"), "42");
+}
+
+#[test]
+fn query_strings_carry_language() {
+    let link = app("https://apps.apple.com/fr/app/facebook/id284882215?l=en-GB&mt=8");
+    assert_eq!(link.app_id, "284882215");
+    assert_eq!(link.storefront.map(|r| r.code), Some("FR"));
+    assert_eq!(link.language.as_deref(), Some("en-GB"));
+
+    let link = app("https://apps.apple.com/app/id284882215?platform=iphone");
+    assert_eq!((link.storefront, link.language), (None, None));
+}
+
+#[test]
+fn deep_links() {
+    assert_eq!(id("itms-apps://itunes.apple.com/app/id284882215"), "284882215");
+    assert_eq!(id("itms-apps://apps.apple.com/gb/app/facebook/id284882215?action=write-review"), "284882215");
+    assert_eq!(id("itms-appss://apps.apple.com/app/id284882215"), "284882215");
+    assert_eq!(id("macappstore://itunes.apple.com/app/id497799835?mt=12"), "497799835");
+    assert_eq!(app("itms-apps://apps.apple.com/gb/app/facebook/id284882215").storefront.map(|r| r.code), Some("GB"));
+}
+
+#[test]
+fn short_links_need_resolving() {
+    assert_eq!(parse_app_link("https://apple.co/3xAbCdE"), Ok(ParsedLink::Short("https://apple.co/3xAbCdE".into())));
+    assert_eq!(parse_app_link("apple.co/3xAbCdE"), Ok(ParsedLink::Short("https://apple.co/3xAbCdE".into())));
+}
+
+#[test]
+fn rejects_other_input() {
+    for input in [
+        "",
+        "facebook",
+        "https://example.com/us/app/facebook/id284882215",
+        "https://apps.apple.com/us/developer/meta-platforms-inc/id284882218",
+        "https://apps.apple.com/us/app-bundle/pocket-ledger-suite/id1500000009",
+        "https://apps.apple.com/us/app/facebook/id",
+        "https://apps.apple.com/us/charts",
+        "ftp://apps.apple.com/app/id1",
+    ] {
+        assert!(parse_app_link(input).is_err(), "{:?} should be rejected", input);
+    }
+}
+
+/// Answers every request with a store page whose canonical URL is `target`.
+struct Redirected {
+    target: &'static str,
+}
+
+#[async_trait]
+impl Transport for Redirected {
+    async fn get(&self, _url: &str) -> Result<Response, TransportError> {
+        Ok(Response::new(200, format!(r#"<html><head><link rel="canonical" href="{}"></head></html>"#, self.target)))
+    }
+}
+
+#[tokio::test]
+async fn client_resolves_short_links() {
+    let client = PricingClient::new()
+        .with_rate_limit(None)
+        .with_transport(Redirected { target: "https://apps.apple.com/jp/app/line/id443904275?l=en" });
+    let link = client.resolve_app_link("https://apple.co/3xAbCdE").await.unwrap();
+    assert_eq!(link.app_id, "443904275");
+    assert_eq!(link.storefront.map(|r| r.code), Some("JP"));
+    assert_eq!(link.language.as_deref(), Some("en"));
+
+    assert_eq!(client.resolve_app_link("id1").await.unwrap().app_id, "1");
+}
@@ -0,0 +1,153 @@
+mod common;
+
+use appstore_pricing::diff::diff_prices;
+use std::time::{Duration, Instant};
+use appstore_pricing::notify::{ChangeEvent, Email, Exec, Fanout, JsonLines, JsonPoster, Notifier, Slack, Webhook};
+use appstore_pricing::transport::HttpConfig;
+use appstore_pricing::{find_region, Pricing};
+use common::smtp::SmtpServer;
+use common::MockServer;
+use serde_json::Value;
+
+fn price(code: &str, amount: f64, currency: &str, converted: f64) -> Pricing {
+    let mut p = Pricing::new(&find_region(code).unwrap(), amount, currency).for_product("com.example.pro");
+    p.converted_amount = Some(converted);
+    p
+}
+
+fn events() -> Vec<ChangeEvent> {
+    let before = [price("JP", 500.0, "JPY", 3.33), price("GB", 4.49, "GBP", 5.61)];
+    let after = [price("JP", 600.0, "JPY", 4.0)];
+    diff_prices(&before, &after)
+        .into_iter()
+        .map(|change| ChangeEvent::new("1234567890", Some("Pocket Ledger"), "USD", change))
+        .collect()
+}
+
+#[test]
+fn events_carry_display_prices_and_deltas() {
+    let events = events();
+    assert_eq!((events[0].old_price.as_deref(), events[0].new_price.as_deref()), (Some("¥500"), Some("¥600")));
+    assert_eq!(events[0].converted_delta.map(|d| (d * 100.0).round() / 100.0), Some(0.67));
+    assert_eq!(events[0].describe(), "Japan (com.example.pro): ¥500 → ¥600 (+20.0%), $3.33 → $4.00 (+$0.67)");
+    assert_eq!(events[1].describe(), "United Kingdom (com.example.pro): no longer sold (was £4.49)");
+}
+
+#[tokio::test]
+async fn json_lines_are_flat_objects() {
+    let sink = JsonLines::new(Vec::new());
+    sink.notify(&events()).await.unwrap();
+    let out = sink.into_inner();
+    let lines: Vec<Value> = String::from_utf8(out).unwrap().lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0]["app_id"], "1234567890");
+    assert_eq!(lines[0]["region_code"], "JP");
+    assert_eq!(lines[0]["change"], "changed");
+    assert_eq!(lines[0]["new_price"], "¥600");
+    assert_eq!(lines[1]["change"], "removed");
+}
+
+/// Posts to `url` with the default HTTP settings.
+fn poster(url: impl Into<String>) -> JsonPoster {
+    JsonPoster::new(url, &HttpConfig::default()).unwrap()
+}
+
+#[tokio::test]
+async fn webhook_posts_events() {
+    let server = MockServer::start();
+    server.route("/hook", 204, "");
+    Webhook(poster(format!("{}/hook", server.url))).notify(&events()).await.unwrap();
+    let posted = server.posted("/hook");
+    assert_eq!(posted.len(), 1);
+    let payload: Value = serde_json::from_str(&posted[0]).unwrap();
+    assert_eq!(payload["events"][0]["product_id"], "com.example.pro");
+    assert_eq!(payload["events"][0]["amount_change_pct"], 20.0);
+    assert_eq!(payload["events"][0]["old_price"], "¥500");
+
+    let missing = Webhook(poster(format!("{}/nowhere", server.url))).notify(&events()).await;
+    assert!(missing.unwrap_err().to_string().contains("HTTP 404"));
+}
+
+#[tokio::test]
+async fn webhooks_use_the_http_settings_and_time_out() {
+    let server = MockServer::start();
+    server.route("/hook", 200, "");
+    let http = HttpConfig { user_agent: "pricing-watch/1.0".to_string(), ..HttpConfig::default() };
+    Webhook(JsonPoster::new(format!("{}/hook", server.url), &http).unwrap()).notify(&events()).await.unwrap();
+    assert_eq!(server.requests()[0].headers["user-agent"], "pricing-watch/1.0");
+
+    // Accepts the connection but never answers.
+    let silent = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/hook", silent.local_addr().unwrap());
+    let started = Instant::now();
+    let impatient = || JsonPoster::new(&url, &http).unwrap().with_timeout(Duration::from_millis(200));
+    let hung = Webhook(impatient()).notify(&events()).await;
+    assert!(hung.is_err());
+    assert!(started.elapsed() < Duration::from_secs(5));
+    let hung = Slack(impatient()).notify(&events()).await;
+    assert!(hung.is_err());
+}
+
+#[tokio::test]
+async fn slack_gets_a_text_message() {
+    let server = MockServer::start();
+    server.route("/services/T0/B0/x", 200, "ok");
+    Slack(poster(format!("{}/services/T0/B0/x", server.url))).notify(&events()).await.unwrap();
+    let payload: Value = serde_json::from_str(&server.posted("/services/T0/B0/x")[0]).unwrap();
+    let text = payload["text"].as_str().unwrap();
+    assert!(text.starts_with("*Pocket Ledger: 2 price changes*\n"), "{}", text);
+    assert!(text.contains("• Japan (com.example.pro): ¥500 → ¥600 (+20.0%)"), "{}", text);
+}
+
+#[tokio::test]
+async fn email_goes_through_smtp() {
+    let smtp = SmtpServer::start();
+    let to = vec!["pricing@example.com".to_string(), "ops@example.com".to_string()];
+    Email::new(&smtp.url, "watch@example.com", &to).unwrap().notify(&events()).await.unwrap();
+    let mail = smtp.received();
+    assert_eq!(mail.len(), 1);
+    assert_eq!(mail[0].from, "watch@example.com");
+    assert_eq!(mail[0].to, to);
+    assert!(mail[0].data.contains("Subject: Pocket Ledger: 2 price changes"), "{}", mail[0].data);
+    assert!(mail[0].data.contains("Japan"), "{}", mail[0].data);
+
+    assert!(Email::new(&smtp.url, "watch@example.com", &[]).is_err());
+    assert!(Email::new(&smtp.url, "not an address", &to).is_err());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn commands_get_events_on_stdin() {
+    let dir = tempfile::tempdir().unwrap();
+    let json = dir.path().join("events.json");
+    let text = dir.path().join("summary.txt");
+    let command = format!("cat > '{}'; printf '%s' \"$APPSTORE_PRICING_SUMMARY\" > '{}'", json.display(), text.display());
+    Exec::new(command).notify(&events()).await.unwrap();
+    let payload: Value = serde_json::from_str(&std::fs::read_to_string(json).unwrap()).unwrap();
+    assert_eq!(payload["events"].as_array().unwrap().len(), 2);
+    assert!(std::fs::read_to_string(text).unwrap().starts_with("Pocket Ledger: 2 price changes\n- Japan"));
+
+    assert!(Exec::new("exit 3").notify(&events()).await.is_err());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn hung_commands_are_stopped() {
+    let started = Instant::now();
+    let hung = Exec::new("sleep 30").with_timeout(Duration::from_millis(200)).notify(&events()).await;
+    assert!(hung.unwrap_err().to_string().contains("didn't finish"));
+    assert!(started.elapsed() < Duration::from_secs(5));
+}
+
+#[tokio::test]
+async fn fanout_tries_every_sink() {
+    let server = MockServer::start();
+    server.route("/hook", 200, "");
+    let fanout = Fanout(vec![
+        Box::new(Webhook(poster(format!("{}/broken", server.url)))),
+        Box::new(Webhook(poster(format!("{}/hook", server.url)))),
+    ]);
+    let err = fanout.notify(&events()).await.unwrap_err();
+    assert!(err.to_string().contains("/broken answered HTTP 404"), "{}", err);
+    assert_eq!(server.posted("/hook").len(), 1);
+}
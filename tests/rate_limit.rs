@@ -0,0 +1,32 @@
+use std::time::{Duration, Instant};
+use appstore_pricing::ratelimit::{RateLimit, RateLimiter};
+
+#[tokio::test]
+async fn burst_then_steady_rate() {
+    let limiter = RateLimiter::new(Some(RateLimit { per_second: 20.0, burst: 2 }));
+    let start = Instant::now();
+    for _ in 0..6 {
+        limiter.acquire("apps.apple.com").await;
+    }
+    // Two free from the burst, then four more at 50ms apiece.
+    assert!(start.elapsed() >= Duration::from_millis(190), "{:?}", start.elapsed());
+}
+
+#[tokio::test]
+async fn hosts_have_separate_buckets() {
+    let limiter = RateLimiter::new(Some(RateLimit { per_second: 1.0, burst: 1 }));
+    let start = Instant::now();
+    limiter.acquire("apps.apple.com").await;
+    limiter.acquire("open.er-api.com").await;
+    assert!(start.elapsed() < Duration::from_millis(500));
+}
+
+#[tokio::test]
+async fn disabled_limiter_never_waits() {
+    let limiter = RateLimiter::new(None);
+    let start = Instant::now();
+    for _ in 0..100 {
+        limiter.acquire("apps.apple.com").await;
+    }
+    assert!(start.elapsed() < Duration::from_millis(100));
+}
@@ -0,0 +1,108 @@
+//! Sanity checks on the storefront table.
+
+use std::collections::HashSet;
+use appstore_pricing::{find_region, home_region, RegionFilter, RegionGroup, REGIONS};
+
+/// Officially assigned ISO 3166-1 alpha-2 codes.
+const ISO_3166: &str = "AD AE AF AG AI AL AM AO AQ AR AS AT AU AW AX AZ BA BB BD BE BF BG BH BI BJ BL BM \
+    BN BO BQ BR BS BT BV BW BY BZ CA CC CD CF CG CH CI CK CL CM CN CO CR CU CV CW CX CY CZ DE DJ DK DM \
+    DO DZ EC EE EG EH ER ES ET FI FJ FK FM FO FR GA GB GD GE GF GG GH GI GL GM GN GP GQ GR GS GT GU GW \
+    GY HK HM HN HR HT HU ID IE IL IM IN IO IQ IR IS IT JE JM JO JP KE KG KH KI KM KN KP KR KW KY KZ LA \
+    LB LC LI LK LR LS LT LU LV LY MA MC MD ME MF MG MH MK ML MM MN MO MP MQ MR MS MT MU MV MW MX MY MZ \
+    NA NC NE NF NG NI NL NO NP NR NU NZ OM PA PE PF PG PH PK PL PM PN PR PS PT PW PY QA RE RO RS RU RW \
+    SA SB SC SD SE SG SH SI SJ SK SL SM SN SO SR SS ST SV SX SY SZ TC TD TF TG TH TJ TK TL TM TN TO TR \
+    TT TV TW TZ UA UG UM US UY UZ VA VC VE VG VI VN VU WF WS YE YT ZA ZM ZW";
+
+/// User-assigned codes Apple uses for storefronts anyway.
+const USER_ASSIGNED: &[&str] = &["XK"];
+
+#[test]
+fn codes_are_unique_iso_3166() {
+    let iso: HashSet<&str> = ISO_3166.split_whitespace().collect();
+    let mut seen = HashSet::new();
+    for r in REGIONS {
+        assert!(iso.contains(r.code) || USER_ASSIGNED.contains(&r.code), "{} is not ISO 3166-1", r.code);
+        assert!(seen.insert(r.code), "{} is listed twice", r.code);
+    }
+}
+
+#[test]
+fn names_and_storefront_ids_are_unique() {
+    let mut names = HashSet::new();
+    let mut ids = HashSet::new();
+    for r in REGIONS {
+        assert!(names.insert(r.name), "{} is listed twice", r.name);
+        assert!(ids.insert(r.storefront_id), "storefront {} is used twice ({})", r.storefront_id, r.code);
+        assert!((143441..=143700).contains(&r.storefront_id), "{}: {}", r.code, r.storefront_id);
+    }
+}
+
+#[test]
+fn currency_and_language_codes_are_well_formed() {
+    for r in REGIONS {
+        assert!(r.currency.len() == 3 && r.currency.chars().all(|c| c.is_ascii_uppercase()), "{}: {}", r.code, r.currency);
+        assert!(r.language.len() == 2 && r.language.chars().all(|c| c.is_ascii_lowercase()), "{}: {}", r.code, r.language);
+    }
+}
+
+#[test]
+fn storefront_metadata() {
+    let nz = find_region("nz").unwrap();
+    assert_eq!((nz.storefront_id, nz.currency, nz.group), (143461, "NZD", RegionGroup::Oceania));
+    assert!(nz.tax_inclusive);
+    let us = find_region("US").unwrap();
+    assert_eq!((us.storefront_id, us.currency, us.language), (143441, "USD", "en"));
+    assert!(!us.tax_inclusive);
+    assert_eq!(find_region("DE").unwrap().group, RegionGroup::Europe);
+}
+
+fn codes(filter: &RegionFilter) -> Vec<&'static str> {
+    filter.select().unwrap().iter().map(|r| r.code).collect()
+}
+
+#[test]
+fn filter_by_codes_keeps_order_and_drops_duplicates() {
+    let filter = RegionFilter { codes: vec!["gb".into(), "US".into(), "GB".into()], ..Default::default() };
+    assert_eq!(codes(&filter), ["GB", "US"]);
+    assert_eq!(RegionFilter::default().select().unwrap().len(), REGIONS.len());
+}
+
+#[test]
+fn filter_by_group_currency_and_exclusion() {
+    let oceania = RegionFilter { groups: vec!["oceania".parse().unwrap()], ..Default::default() };
+    assert_eq!(codes(&oceania), ["FJ", "FM", "NR", "NZ", "PG", "SB", "TO", "VU"]);
+
+    let eurozone = RegionFilter {
+        groups: vec![RegionGroup::Europe],
+        currencies: vec!["eur".into()],
+        exclude: vec!["DE".into(), "FR".into()],
+        ..Default::default()
+    };
+    let eurozone = codes(&eurozone);
+    assert!(eurozone.contains(&"AT") && !eurozone.contains(&"DE") && !eurozone.contains(&"CH"));
+
+    let chf = RegionFilter { currencies: vec!["CHF".into()], ..Default::default() };
+    assert_eq!(codes(&chf), ["LI", "CH"]);
+}
+
+#[test]
+fn filter_errors() {
+    let unknown = RegionFilter { exclude: vec!["ZZ".into()], ..Default::default() };
+    assert!(unknown.select().unwrap_err().to_string().contains("Unknown region code: ZZ"));
+    let nothing = RegionFilter { codes: vec!["US".into()], groups: vec![RegionGroup::Europe], ..Default::default() };
+    assert!(nothing.select().is_err());
+    assert!("antarctica".parse::<RegionGroup>().is_err());
+}
+
+#[test]
+fn currencies_map_to_home_storefronts() {
+    let home = |currency: &str| home_region(currency).map(|r| r.code);
+    assert_eq!(home("EUR"), Some("DE"));
+    assert_eq!(home("usd"), Some("US"));
+    assert_eq!(home("XOF"), Some("SN"));
+    assert_eq!(home("GBP"), Some("GB"));
+    assert_eq!(home("JPY"), Some("JP"));
+    // Sold in US dollars in Apple's storefront, but the code still names the country.
+    assert_eq!(home("KES"), Some("KE"));
+    assert_eq!(home("XPF"), None);
+}
@@ -0,0 +1,115 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use appstore_pricing::ratelimit::{RateLimit, RateLimiter};
+use appstore_pricing::retry::{RetryPolicy, Retrying};
+use appstore_pricing::transport::{Response, Transport, TransportError};
+use async_trait::async_trait;
+
+/// Plays back a fixed sequence of results and counts the calls.
+struct Scripted {
+    script: Mutex<VecDeque<Result<Response, TransportError>>>,
+    calls: Mutex<usize>,
+    delay: Duration,
+}
+
+impl Scripted {
+    fn new(script: Vec<Result<Response, TransportError>>) -> Arc<Self> {
+        Arc::new(Scripted { script: Mutex::new(script.into()), calls: Mutex::new(0), delay: Duration::ZERO })
+    }
+
+    fn calls(&self) -> usize {
+        *self.calls.lock().unwrap()
+    }
+}
+
+#[async_trait]
+impl Transport for Scripted {
+    async fn get(&self, _url: &str) -> Result<Response, TransportError> {
+        *self.calls.lock().unwrap() += 1;
+        tokio::time::sleep(self.delay).await;
+        self.script.lock().unwrap().pop_front().unwrap_or_else(|| Ok(Response::new(200, "ok")))
+    }
+}
+
+fn quick() -> RetryPolicy {
+    RetryPolicy {
+        max_retries: 3,
+        base_delay: Duration::from_millis(1),
+        max_delay: Duration::from_millis(20),
+        timeout: Duration::from_secs(5),
+    }
+}
+
+fn with_retry_after(status: u16, value: &str) -> Response {
+    let mut res = Response::new(status, "");
+    res.headers.insert("retry-after".into(), value.into());
+    res
+}
+
+#[tokio::test]
+async fn retries_transient_statuses_until_success() {
+    let inner = Scripted::new(vec![
+        Ok(Response::new(503, "")),
+        Err(TransportError::Request("connection reset".into())),
+        Ok(Response::new(429, "")),
+    ]);
+    let res = Retrying::new(inner.clone(), quick()).get("http://example.test/").await.unwrap();
+    assert_eq!(res.status, 200);
+    assert_eq!(inner.calls(), 4);
+}
+
+#[tokio::test]
+async fn gives_up_with_last_response() {
+    let inner = Scripted::new(vec![Ok(Response::new(429, "")); 10]);
+    let res = Retrying::new(inner.clone(), quick()).get("http://example.test/").await.unwrap();
+    assert_eq!(res.status, 429);
+    assert_eq!(inner.calls(), 4);
+}
+
+#[tokio::test]
+async fn does_not_retry_client_errors() {
+    let inner = Scripted::new(vec![Ok(Response::new(404, ""))]);
+    let res = Retrying::new(inner.clone(), quick()).get("http://example.test/").await.unwrap();
+    assert_eq!(res.status, 404);
+    assert_eq!(inner.calls(), 1);
+}
+
+#[tokio::test]
+async fn honors_retry_after_up_to_max_delay() {
+    let inner = Scripted::new(vec![Ok(with_retry_after(429, "1"))]);
+    let policy = RetryPolicy { max_delay: Duration::from_millis(150), ..quick() };
+    let start = Instant::now();
+    let res = Retrying::new(inner, policy).get("http://example.test/").await.unwrap();
+    assert_eq!(res.status, 200);
+    let elapsed = start.elapsed();
+    assert!(elapsed >= Duration::from_millis(150) && elapsed < Duration::from_millis(900), "{:?}", elapsed);
+}
+
+#[tokio::test]
+async fn slow_attempts_time_out() {
+    let inner = Arc::new(Scripted {
+        script: Mutex::new(VecDeque::new()),
+        calls: Mutex::new(0),
+        delay: Duration::from_millis(200),
+    });
+    let policy = RetryPolicy { max_retries: 1, timeout: Duration::from_millis(20), ..quick() };
+    let res = Retrying::new(inner.clone(), policy).get("http://example.test/").await;
+    assert!(matches!(res, Err(TransportError::Timeout)), "{:?}", res);
+    assert_eq!(inner.calls(), 2);
+}
+
+#[tokio::test]
+async fn waiting_for_the_rate_limit_is_not_a_timeout() {
+    let inner = Scripted::new(vec![]);
+    let limiter = Arc::new(RateLimiter::new(Some(RateLimit { per_second: 5.0, burst: 1 })));
+    let policy = RetryPolicy { max_retries: 0, timeout: Duration::from_millis(50), ..quick() };
+    let retrying = Retrying::new(inner.clone(), policy).with_limiter(limiter);
+    let start = Instant::now();
+    for _ in 0..3 {
+        // Each request after the first waits 200ms for a token, four times the timeout.
+        assert_eq!(retrying.get("http://example.test/").await.unwrap().status, 200);
+    }
+    assert!(start.elapsed() >= Duration::from_millis(350), "{:?}", start.elapsed());
+    assert_eq!(inner.calls(), 3);
+}
@@ -0,0 +1,60 @@
+//! Subscription periods, introductory offers and per-month normalisation.
+
+use std::collections::HashMap;
+use appstore_pricing::extract::{self, Page};
+use appstore_pricing::{convert_prices, find_region, period_in_months, Pricing};
+
+fn subscriptions() -> Vec<Pricing> {
+    let path = format!("{}/tests/fixtures/iap_subscriptions.html", env!("CARGO_MANIFEST_DIR"));
+    let page = Page::new(200, std::fs::read_to_string(path).unwrap());
+    extract::all_iap_prices(&page, &find_region("US").unwrap(), &[]).unwrap().unwrap()
+}
+
+fn by_product<'a>(prices: &'a [Pricing], suffix: &str) -> &'a Pricing {
+    prices
+        .iter()
+        .find(|p| p.product_id.as_deref().is_some_and(|id| id.ends_with(suffix)))
+        .unwrap()
+}
+
+#[test]
+fn periods_in_months() {
+    assert_eq!(period_in_months("P1M"), Some(1.0));
+    assert_eq!(period_in_months("P1Y"), Some(12.0));
+    assert_eq!(period_in_months("P6M"), Some(6.0));
+    assert!((period_in_months("P1W").unwrap() - 0.23).abs() < 0.01);
+    assert_eq!(period_in_months("P1Y6M"), Some(18.0));
+    assert_eq!(period_in_months("1M"), None);
+    assert_eq!(period_in_months("P0D"), None);
+    assert_eq!(period_in_months("PXM"), None);
+}
+
+#[test]
+fn offers_carry_kind_period_and_intro() {
+    let prices = subscriptions();
+
+    let monthly = by_product(&prices, ".pro.monthly");
+    assert_eq!(monthly.offer_type.as_deref(), Some("auto-renewable-subscription"));
+    assert_eq!(monthly.billing_period.as_deref(), Some("P1M"));
+    assert_eq!(monthly.intro_offer.as_deref(), Some("free-trial"));
+    assert_eq!((monthly.intro_price, monthly.intro_period.as_deref(), monthly.intro_periods), (Some(0.0), Some("P1W"), Some(1)));
+
+    let yearly = by_product(&prices, ".pro.yearly");
+    assert_eq!(yearly.intro_offer.as_deref(), Some("pay-as-you-go"));
+    assert_eq!((yearly.intro_price, yearly.intro_periods), (Some(0.99), Some(3)));
+
+    let lifetime = by_product(&prices, ".lifetime");
+    assert_eq!(lifetime.offer_type.as_deref(), Some("non-consumable"));
+    assert_eq!((lifetime.billing_period.as_deref(), lifetime.intro_offer.as_deref()), (None, None));
+}
+
+#[test]
+fn conversion_normalises_subscriptions_per_month() {
+    let mut prices = subscriptions();
+    convert_prices(&mut prices, "EUR", &HashMap::from([("USD".to_string(), 1.25)]));
+
+    assert_eq!(by_product(&prices, ".pro.monthly").converted_monthly, Some(3.99));
+    assert_eq!(by_product(&prices, ".pro.yearly").converted_monthly, Some(2.67));
+    assert_eq!(by_product(&prices, ".pro.weekly").converted_monthly, Some(6.92));
+    assert_eq!(by_product(&prices, ".lifetime").converted_monthly, None);
+}